@@ -0,0 +1,679 @@
+//! A command-line tool for working with Whiley test files.
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use whiley_test_file::diagnostics::{DiagnosticParser, TextParser};
+use whiley_test_file::options::Options;
+use whiley_test_file::report::{JUnit, Tap};
+use whiley_test_file::runner::{Outcome, Runner, Summary};
+use whiley_test_file::syntax::SyntaxTree;
+use whiley_test_file::{Action, Change, ErrorKind, Materialiser, Severity, WhileyTestFile};
+
+static USAGE: &str = "usage: wtf <command> [args]
+
+commands:
+    parse <file>                        print file in canonical form
+    check <path>...                     check files (or directories of .test files)
+    fmt [--check] <path>...             reformat files in place (or check formatting)
+    dump <file>                         print parsed structure of file
+    stats <path>...                     summarise a corpus of test files
+    bless <file> <actual>               rewrite expected markers from those in actual
+    materialise <file> <frame> <dir>    write project state after frame into dir
+    run [options] <path>... -- <cmd>    run compiler command on each frame of files
+
+run options:
+    --timeout <secs>                    time allowed for each frame (default 10)
+    --messages <file>                   error codes for messages (lines `errno message`)
+    --ignore <key>                      accept config option (or namespace ending `.`)
+    --format <text|junit|tap>           format of results printed (default text)
+    --json                              parse compiler output as JSON (requires json feature)
+
+exit codes:
+    0       success
+    1       file not formatted (fmt --check)
+    2       invalid command line
+    3       file could not be read or written
+    4       frame could not be applied
+    5       test run failed
+    10      file could not be parsed: unexpected end-of-file
+    11      ... invalid configuration option
+    12      ... invalid configuration value
+    13      ... invalid integer value
+    14      ... invalid string value
+    15      ... invalid action
+    16      ... invalid range
+    17      ... invalid marker
+    18      ... invalid error code
+    19      ... invalid coordinate
+    20      ... invalid filename
+    21      ... unexpected no newline marker
+    22      ... unexpected line";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let failures = match args.first().map(String::as_str) {
+        Some("parse") => parse(&args[1..]),
+        Some("check") => check(&args[1..]),
+        Some("fmt") => format(&args[1..]),
+        Some("dump") => dump(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("bless") => bless(&args[1..]),
+        Some("materialise") => materialise(&args[1..]),
+        Some("run") => run(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    for f in &failures {
+        eprintln!("wtf: {}", f);
+    }
+    // Exit code determined by first failure
+    match failures.first() {
+        None => ExitCode::SUCCESS,
+        Some(f) => ExitCode::from(f.code()),
+    }
+}
+
+// ===============================================================
+// Failure
+// ===============================================================
+
+/// Identifies a problem which caused a command to fail.  Each kind of
+/// problem has a distinct exit code.
+#[derive(Debug)]
+enum Failure {
+    /// A file is not formatted.
+    Unformatted(String),
+    /// The command line was malformed.
+    Usage(String),
+    /// A file could not be read or written.
+    Io(String),
+    /// A frame could not be applied.
+    Snapshot(String),
+    /// A test run did not succeed.
+    Run(String),
+    /// A file could not be parsed.
+    Parse(ErrorKind, String),
+}
+
+impl Failure {
+    /// Get the exit code for this failure.
+    fn code(&self) -> u8 {
+        match self {
+            Failure::Unformatted(_) => 1,
+            Failure::Usage(_) => 2,
+            Failure::Io(_) => 3,
+            Failure::Snapshot(_) => 4,
+            Failure::Run(_) => 5,
+            Failure::Parse(k, _) => parse_code(*k),
+        }
+    }
+}
+
+/// Get the exit code for a given kind of parse error.  These are
+/// fixed, since hooks may rely on them.
+fn parse_code(kind: ErrorKind) -> u8 {
+    match kind {
+        ErrorKind::UnexpectedEof => 10,
+        ErrorKind::InvalidConfigOption => 11,
+        ErrorKind::InvalidConfigValue => 12,
+        ErrorKind::InvalidIntValue => 13,
+        ErrorKind::InvalidStringValue => 14,
+        ErrorKind::InvalidAction => 15,
+        ErrorKind::InvalidRange => 16,
+        ErrorKind::InvalidMarker => 17,
+        ErrorKind::InvalidErrorCode => 18,
+        ErrorKind::InvalidCoordinate => 19,
+        ErrorKind::InvalidFilename => 20,
+        ErrorKind::UnexpectedNoNewline => 21,
+        ErrorKind::UnexpectedLine => 22,
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Unformatted(m)
+            | Failure::Usage(m)
+            | Failure::Io(m)
+            | Failure::Snapshot(m)
+            | Failure::Run(m)
+            | Failure::Parse(_, m) => f.write_str(m),
+        }
+    }
+}
+
+type Failures = Vec<Failure>;
+
+// ===============================================================
+// Commands
+// ===============================================================
+
+/// Parse a test file and print it in canonical form.
+fn parse(args: &[String]) -> Failures {
+    let filename = match args {
+        [f] => f,
+        _ => return usage("expected <file>"),
+    };
+    let input = match read(Path::new(filename)) {
+        Ok(input) => input,
+        Err(f) => return vec![f],
+    };
+    match WhileyTestFile::new(&input) {
+        Ok(wtf) => {
+            print!("{}", wtf);
+            Vec::new()
+        }
+        Err(e) => vec![Failure::Parse(e.kind, format!("{}: {}", filename, e))],
+    }
+}
+
+/// Check that every test file parses without errors, and that each
+/// of its frames can be applied in turn.  Every error found is
+/// reported, rather than just the first.
+fn check(args: &[String]) -> Failures {
+    let mut failures = Failures::new();
+    for path in collect(args, &mut failures) {
+        let filename = path.display();
+        let input = match read(&path) {
+            Ok(input) => input,
+            Err(f) => {
+                failures.push(f);
+                continue;
+            }
+        };
+        let (wtf, errors) = WhileyTestFile::new_recovering(&input);
+        if !errors.is_empty() {
+            for e in errors {
+                failures.push(Failure::Parse(e.kind, format!("{}: {}", filename, e)));
+            }
+            continue;
+        }
+        for (i, snapshot) in wtf.snapshots().enumerate() {
+            if let Err(e) = snapshot {
+                failures.push(Failure::Snapshot(format!(
+                    "{}: frame {}: {}",
+                    filename, i, e
+                )));
+            }
+        }
+    }
+    failures
+}
+
+/// Reformat test files in place, or just check whether they are
+/// formatted (when `--check` is given).
+fn format(args: &[String]) -> Failures {
+    let (check, args) = match args.first().map(String::as_str) {
+        Some("--check") => (true, &args[1..]),
+        _ => (false, args),
+    };
+    let mut failures = Failures::new();
+    for path in collect(args, &mut failures) {
+        let filename = path.display();
+        let input = match read(&path) {
+            Ok(input) => input,
+            Err(f) => {
+                failures.push(f);
+                continue;
+            }
+        };
+        let wtf = match WhileyTestFile::new(&input) {
+            Ok(wtf) => wtf,
+            Err(e) => {
+                failures.push(Failure::Parse(e.kind, format!("{}: {}", filename, e)));
+                continue;
+            }
+        };
+        let mut output = wtf.to_string();
+        // Control lines follow the terminator of the first line
+        if input.find('\n').is_some_and(|i| input[..i].ends_with('\r')) {
+            output = crlf(&output);
+        }
+        // Never write output which would lose something
+        if WhileyTestFile::new(&output).ok().as_ref() != Some(&wtf) {
+            failures.push(Failure::Unformatted(format!(
+                "{}: cannot be formatted without loss",
+                filename
+            )));
+            continue;
+        }
+        if input == output {
+            continue;
+        } else if check {
+            failures.push(Failure::Unformatted(format!("{}: not formatted", filename)));
+        } else if let Err(e) = fs::write(&path, output) {
+            failures.push(Failure::Io(format!("{}: {}", filename, e)));
+        } else {
+            println!("formatted {}", filename);
+        }
+    }
+    failures
+}
+
+/// Terminate every line with `\r\n`, including those which are
+/// currently terminated with only `\n`.
+fn crlf(text: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|l| match l.strip_suffix('\n') {
+            Some(l) => format!("{}\r\n", l.strip_suffix('\r').unwrap_or(l)),
+            None => l.to_string(),
+        })
+        .collect()
+}
+
+/// Print the parsed structure of a test file.
+fn dump(args: &[String]) -> Failures {
+    let filename = match args {
+        [f] => f,
+        _ => return usage("expected <file>"),
+    };
+    let input = match read(Path::new(filename)) {
+        Ok(input) => input,
+        Err(f) => return vec![f],
+    };
+    let wtf = match WhileyTestFile::new(&input) {
+        Ok(wtf) => wtf,
+        Err(e) => return vec![Failure::Parse(e.kind, format!("{}: {}", filename, e))],
+    };
+    // Config options (sorted for determinism)
+    let mut config: Vec<_> = wtf.config().collect();
+    config.sort_by_key(|(k, _)| *k);
+    println!("config:");
+    for (k, v) in config {
+        println!("    {} = {}", k, v);
+    }
+    // Frames
+    for (i, frame) in wtf.iter().enumerate() {
+        println!("frame {}:", i);
+        for action in &frame.actions {
+            match action {
+                Action::CREATE(f, lines, _) => {
+                    println!("    create {} ({} lines)", f, lines.len())
+                }
+                Action::INSERT(f, r, lines, _) => {
+                    println!("    insert {} {} ({} lines)", f, r, lines.len())
+                }
+                Action::REMOVE(f) => println!("    remove {}", f),
+            }
+        }
+        for marker in &frame.markers {
+            println!("    marker {}", marker);
+        }
+    }
+    Vec::new()
+}
+
+/// Summarise a corpus of test files.  Files which cannot be parsed
+/// are reported, but otherwise ignored.
+fn stats(args: &[String]) -> Failures {
+    let mut failures = Failures::new();
+    let mut stats = Stats::default();
+    for path in collect(args, &mut failures) {
+        let input = match read(&path) {
+            Ok(input) => input,
+            Err(f) => {
+                failures.push(f);
+                continue;
+            }
+        };
+        match WhileyTestFile::new(&input) {
+            Ok(wtf) => stats.add(&wtf),
+            Err(e) => {
+                let msg = format!("{}: {}", path.display(), e);
+                failures.push(Failure::Parse(e.kind, msg));
+            }
+        }
+    }
+    print!("{}", stats);
+    failures
+}
+
+/// Rewrite the expected markers of a test file with those actually
+/// produced.  The actual markers are given as a test file with the
+/// same number of frames (whose actions are ignored), such as:
+///
+/// ```text
+/// ====
+/// ---
+/// E101 main.whiley 1,2
+/// ====
+/// ```
+///
+/// Only marker blocks are rewritten, and each changed line is printed.
+fn bless(args: &[String]) -> Failures {
+    let (filename, actual) = match args {
+        [f, a] => (f, a),
+        _ => return usage("expected <file> <actual>"),
+    };
+    let (input, markers) = match (read(Path::new(filename)), read(Path::new(actual))) {
+        (Ok(i), Ok(m)) => (i, m),
+        (Err(f), _) | (_, Err(f)) => return vec![f],
+    };
+    let markers: Vec<_> = match WhileyTestFile::new(&markers) {
+        Ok(wtf) => wtf.iter().map(|f| f.markers.clone()).collect(),
+        Err(e) => return vec![Failure::Parse(e.kind, format!("{}: {}", actual, e))],
+    };
+    let mut tree = SyntaxTree::new(&input);
+    if let Err(e) = tree.parse() {
+        return vec![Failure::Parse(e.kind, format!("{}: {}", filename, e))];
+    }
+    if tree.frames.len() != markers.len() {
+        return usage(&format!(
+            "{}: expected {} frames, found {}",
+            actual,
+            tree.frames.len(),
+            markers.len()
+        ));
+    }
+    let changes = tree.bless(&markers);
+    if changes.is_empty() {
+        return Vec::new();
+    }
+    if let Err(e) = fs::write(filename, tree.to_string()) {
+        return vec![Failure::Io(format!("{}: {}", filename, e))];
+    }
+    for c in changes {
+        println!("{}", c);
+    }
+    Vec::new()
+}
+
+/// Write the state of the project after a given frame into a target
+/// directory.  Files belonging to earlier frames which are no longer
+/// present are removed, whilst files which are unchanged are not
+/// written.
+fn materialise(args: &[String]) -> Failures {
+    let (filename, frame, dir) = match args {
+        [f, n, d] => (f, n, d),
+        _ => return usage("expected <file> <frame> <dir>"),
+    };
+    let frame: usize = match frame.parse() {
+        Ok(n) => n,
+        Err(_) => return usage(&format!("invalid frame \"{}\"", frame)),
+    };
+    let input = match read(Path::new(filename)) {
+        Ok(input) => input,
+        Err(f) => return vec![f],
+    };
+    let wtf = match WhileyTestFile::new(&input) {
+        Ok(wtf) => wtf,
+        Err(e) => return vec![Failure::Parse(e.kind, format!("{}: {}", filename, e))],
+    };
+    if frame >= wtf.size() {
+        return usage(&format!("{}: no frame {}", filename, frame));
+    }
+    let mut target = Materialiser::new(dir);
+    for (i, snapshot) in wtf.snapshots().enumerate() {
+        let snapshot = match snapshot {
+            Ok(s) => s,
+            Err(e) => {
+                return vec![Failure::Snapshot(format!(
+                    "{}: frame {}: {}",
+                    filename, i, e
+                ))]
+            }
+        };
+        if i < frame {
+            target.track(&snapshot);
+        } else {
+            let changes = match target.write(&snapshot) {
+                Ok(changes) => changes,
+                Err(e) => return vec![Failure::Io(e.to_string())],
+            };
+            for c in changes {
+                match c {
+                    Change::Write(f) => println!("wrote {}", f),
+                    Change::Remove(f) => println!("removed {}", f),
+                }
+            }
+            break;
+        }
+    }
+    Vec::new()
+}
+
+/// Run a compiler command on each frame of a set of test files,
+/// checking the markers it produces against those expected.
+fn run(args: &[String]) -> Failures {
+    let split = args.iter().position(|a| a == "--");
+    let (mut args, command) = match split {
+        Some(i) if i + 1 < args.len() => (&args[..i], args[i + 1..].to_vec()),
+        _ => return usage("expected <path>... -- <command>"),
+    };
+    let mut timeout = Duration::from_secs(10);
+    let mut parser: Box<dyn DiagnosticParser> = Box::new(TextParser::new());
+    let mut options = Options::wyc();
+    let mut format = "text";
+    loop {
+        match args {
+            [o, secs, rest @ ..] if o == "--timeout" => {
+                match secs.parse().map(Duration::try_from_secs_f64) {
+                    Ok(Ok(t)) => timeout = t,
+                    _ => return usage(&format!("invalid timeout \"{}\"", secs)),
+                }
+                args = rest;
+            }
+            [o, file, rest @ ..] if o == "--messages" => {
+                match messages(Path::new(file)) {
+                    Ok(p) => parser = Box::new(p),
+                    Err(f) => return vec![f],
+                }
+                args = rest;
+            }
+            [o, f, rest @ ..] if o == "--format" => {
+                match f.as_str() {
+                    "text" | "junit" | "tap" => format = f,
+                    _ => return usage(&format!("invalid format \"{}\"", f)),
+                }
+                args = rest;
+            }
+            [o, key, rest @ ..] if o == "--ignore" => {
+                options = options.ignore(key.as_str());
+                args = rest;
+            }
+            #[cfg(feature = "json")]
+            [o, rest @ ..] if o == "--json" => {
+                parser = Box::new(whiley_test_file::diagnostics::JsonParser);
+                args = rest;
+            }
+            _ => break,
+        }
+    }
+    // Resolve relative programs before changing directory
+    let mut command = command;
+    if command[0].contains('/') {
+        if let Ok(p) = fs::canonicalize(&command[0]) {
+            command[0] = p.display().to_string();
+        }
+    }
+    let runner = Runner::new(command, parser)
+        .options(options)
+        .timeout(timeout);
+    let mut failures = Failures::new();
+    let mut summary = Summary::default();
+    let mut results = Vec::new();
+    for path in collect(args, &mut failures) {
+        let filename = path.display().to_string();
+        let input = match read(&path) {
+            Ok(input) => input,
+            Err(f) => {
+                failures.push(f);
+                continue;
+            }
+        };
+        let wtf = match WhileyTestFile::new(&input) {
+            Ok(wtf) => wtf,
+            Err(e) => {
+                failures.push(Failure::Parse(e.kind, format!("{}: {}", filename, e)));
+                continue;
+            }
+        };
+        let result = runner.run(&filename, &wtf);
+        if format == "text" {
+            for f in &result.frames {
+                println!("{} frame {}: {}", filename, f.frame, f.outcome);
+                if let Outcome::Fail(cmp) = &f.outcome {
+                    for line in cmp.to_string().lines() {
+                        println!("    {}", line);
+                    }
+                }
+            }
+        }
+        summary.add(&result);
+        results.push(result);
+    }
+    match format {
+        "junit" => print!("{}", JUnit(&results)),
+        "tap" => print!("{}", Tap(&results)),
+        _ => println!("{}", summary),
+    }
+    if !summary.is_success() {
+        failures.push(Failure::Run(summary.to_string()));
+    }
+    failures
+}
+
+/// Construct a text parser from a file associating messages with
+/// error codes, where each line is of the form `errno message` (blank
+/// lines and those beginning with `#` are ignored).
+fn messages(path: &Path) -> Result<TextParser, Failure> {
+    let mut parser = TextParser::new();
+    for line in read(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once(' ').map(|(e, m)| (e.parse(), m.trim())) {
+            Some((Ok(errno), message)) => parser = parser.message(message, errno),
+            _ => {
+                return Err(Failure::Usage(format!(
+                    "{}: invalid message \"{}\"",
+                    path.display(),
+                    line
+                )))
+            }
+        }
+    }
+    Ok(parser)
+}
+
+// ===============================================================
+// Stats
+// ===============================================================
+
+/// Summary statistics for a corpus of test files.
+#[derive(Default)]
+struct Stats {
+    files: usize,
+    frames: usize,
+    creates: usize,
+    inserts: usize,
+    removes: usize,
+    errors: usize,
+    warnings: usize,
+    others: usize,
+}
+
+impl Stats {
+    /// Include a given test file in these statistics.
+    fn add(&mut self, wtf: &WhileyTestFile) {
+        self.files += 1;
+        for frame in wtf.iter() {
+            self.frames += 1;
+            for action in &frame.actions {
+                match action {
+                    Action::CREATE(..) => self.creates += 1,
+                    Action::INSERT(..) => self.inserts += 1,
+                    Action::REMOVE(..) => self.removes += 1,
+                }
+            }
+            for marker in &frame.markers {
+                match marker.severity {
+                    Severity::Error => self.errors += 1,
+                    Severity::Warning => self.warnings += 1,
+                    _ => self.others += 1,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "files:   {}", self.files)?;
+        writeln!(f, "frames:  {}", self.frames)?;
+        writeln!(
+            f,
+            "actions: {} (create {}, insert {}, remove {})",
+            self.creates + self.inserts + self.removes,
+            self.creates,
+            self.inserts,
+            self.removes
+        )?;
+        writeln!(
+            f,
+            "markers: {} (error {}, warning {}, other {})",
+            self.errors + self.warnings + self.others,
+            self.errors,
+            self.warnings,
+            self.others
+        )
+    }
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+/// Construct a failure for a malformed command line.
+fn usage(msg: &str) -> Failures {
+    vec![Failure::Usage(msg.to_string())]
+}
+
+/// Read a file into a string.
+fn read(path: &Path) -> Result<String, Failure> {
+    fs::read_to_string(path).map_err(|e| Failure::Io(format!("{}: {}", path.display(), e)))
+}
+
+/// Collect the test files identified by a given set of paths.  Files
+/// are included as is, whilst directories are searched recursively
+/// for files with the `.test` extension.
+fn collect(args: &[String], failures: &mut Failures) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if args.is_empty() {
+        failures.push(Failure::Usage("expected <path>...".to_string()));
+    }
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            if let Err(e) = collect_dir(path, &mut files) {
+                failures.push(Failure::Io(format!("{}: {}", arg, e)));
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    files
+}
+
+/// Recursively collect the test files within a given directory (in
+/// order of their names).
+fn collect_dir(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "test") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
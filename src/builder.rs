@@ -0,0 +1,280 @@
+use crate::{
+    Action, Config, Coordinate, Frame, Marker, PathError, Range, Severity, TestPath, Value,
+    Violation, WhileyTestFile,
+};
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::result;
+
+// ===============================================================
+// Build Error
+// ===============================================================
+
+/// Represents a problem encountered when building a test file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// A configuration key which is empty, or contains whitespace or
+    /// `=`.
+    InvalidConfigKey(String),
+    /// A string configuration value which contains a quote, `=` or
+    /// line terminator.
+    InvalidConfigValue(String),
+    /// A marker severity whose letter is not alphabetic, or which
+    /// would be read back as a different severity.
+    InvalidSeverity(Severity),
+    /// A filename which is not a valid path (see [`TestPath`]).
+    InvalidFilename(PathError),
+    /// A line which contains a newline, and so could not be parsed
+    /// back.
+    InvalidLine(String),
+    /// The test file is not valid (see [`WhileyTestFile::validate`]).
+    Violation(Violation),
+    /// The printed test file does not parse back to the same test
+    /// file.
+    Irreversible,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::InvalidConfigKey(k) => write!(f, "invalid configuration key \"{}\"", k),
+            BuildError::InvalidConfigValue(v) => {
+                write!(f, "invalid configuration value \"{}\"", v)
+            }
+            BuildError::InvalidSeverity(s) => write!(f, "invalid severity '{}'", s.to_char()),
+            BuildError::InvalidFilename(e) => write!(f, "invalid filename ({})", e),
+            BuildError::InvalidLine(l) => write!(f, "invalid line \"{}\"", l),
+            BuildError::Violation(v) => write!(f, "{}", v),
+            BuildError::Irreversible => write!(f, "test file cannot be parsed back"),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
+pub type BuildResult<T> = result::Result<T, BuildError>;
+
+// ===============================================================
+// Test File Builder
+// ===============================================================
+
+/// Responsible for constructing test files programmatically.  The
+/// resulting test file is checked on [`build()`](Self::build), so
+/// that it can always be printed and parsed back again.
+///
+/// ```
+/// use whiley_test_file::{Coordinate, Range, WhileyTestFileBuilder};
+///
+/// let wtf = WhileyTestFileBuilder::new()
+///     .config("whiley.verify", false)
+///     .frame(|f| {
+///         f.create("main.whiley", ["method main():"])
+///             .expect_error(101, "main.whiley", Coordinate(1, Range(7, 10)))
+///     })
+///     .frame(|f| f.insert("main.whiley", Range(1, 1), ["method main()", "    skip"]))
+///     .build()
+///     .unwrap();
+/// assert_eq!(wtf.size(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WhileyTestFileBuilder<'a> {
+    config: Config<'a>,
+    frames: Vec<Frame<'a>>,
+    error: Option<BuildError>,
+}
+
+impl<'a> WhileyTestFileBuilder<'a> {
+    /// Construct a builder for an empty test file.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a configuration option, replacing any existing value.
+    pub fn config<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Value<'a>>,
+    {
+        self.config.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a frame whose actions and markers are determined by a given
+    /// function.
+    pub fn frame<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(FrameBuilder<'a>) -> FrameBuilder<'a>,
+    {
+        let frame = f(FrameBuilder::new());
+        self.error = self.error.or(frame.error);
+        self.frames.push(frame.frame);
+        self
+    }
+
+    /// Construct the test file, checking that it can be printed and
+    /// parsed back again (to the same test file), and that it is
+    /// valid.
+    pub fn build(self) -> BuildResult<WhileyTestFile<'a>> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        for (k, v) in &self.config {
+            if k.is_empty() || k.contains(|c: char| c.is_whitespace() || c == '=') {
+                return Err(BuildError::InvalidConfigKey(k.to_string()));
+            }
+            match v {
+                Value::String(s) if s.contains(['"', '=', '\n', '\r']) => {
+                    return Err(BuildError::InvalidConfigValue(s.to_string()));
+                }
+                _ => {}
+            }
+        }
+        for frame in &self.frames {
+            for marker in &frame.markers {
+                let c = marker.severity.to_char();
+                if !c.is_ascii_alphabetic() || Severity::from_char(c) != marker.severity {
+                    return Err(BuildError::InvalidSeverity(marker.severity));
+                }
+            }
+            for action in &frame.actions {
+                if let Action::CREATE(_, lines, _) | Action::INSERT(_, _, lines, _) = action {
+                    for l in lines {
+                        if l.contains('\n') {
+                            return Err(BuildError::InvalidLine(l.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        let wtf = WhileyTestFile {
+            config: self.config,
+            frames: self.frames,
+        };
+        if WhileyTestFile::new(&wtf.to_string()).ok().as_ref() != Some(&wtf) {
+            return Err(BuildError::Irreversible);
+        }
+        match wtf.validate().into_iter().next() {
+            Some(v) => Err(BuildError::Violation(v)),
+            None => Ok(wtf),
+        }
+    }
+}
+
+// ===============================================================
+// Frame Builder
+// ===============================================================
+
+/// Responsible for constructing a single frame of a test file.
+/// Actions and markers appear in the order they are added.
+#[derive(Clone, Debug)]
+pub struct FrameBuilder<'a> {
+    frame: Frame<'a>,
+    error: Option<BuildError>,
+}
+
+impl<'a> FrameBuilder<'a> {
+    fn new() -> Self {
+        FrameBuilder {
+            frame: Frame {
+                actions: Vec::new(),
+                markers: Vec::new(),
+            },
+            error: None,
+        }
+    }
+
+    /// Set the contents of a file.
+    pub fn create<N, I>(mut self, filename: N, lines: I) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+        I: IntoIterator,
+        I::Item: Into<Cow<'a, str>>,
+    {
+        let lines = lines.into_iter().map(Into::into).collect();
+        match self.path(filename) {
+            Some(f) => self.action(Action::CREATE(f, lines, true)),
+            None => self,
+        }
+    }
+
+    /// Replace lines `i` through `j` (inclusive, numbered from `1`) of
+    /// an existing file, where `i:i-1` inserts before line `i` without
+    /// replacing any.
+    pub fn insert<N, I>(mut self, filename: N, range: Range, lines: I) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+        I: IntoIterator,
+        I::Item: Into<Cow<'a, str>>,
+    {
+        let lines = lines.into_iter().map(Into::into).collect();
+        match self.path(filename) {
+            Some(f) => self.action(Action::INSERT(f, range, lines, true)),
+            None => self,
+        }
+    }
+
+    /// Delete a file.
+    pub fn remove<N: Into<Cow<'a, str>>>(mut self, filename: N) -> Self {
+        match self.path(filename) {
+            Some(f) => self.action(Action::REMOVE(f)),
+            None => self,
+        }
+    }
+
+    /// Add an arbitrary action.
+    pub fn action(mut self, action: Action<'a>) -> Self {
+        self.frame.actions.push(action);
+        self
+    }
+
+    /// Expect an error at a given location.
+    pub fn expect_error<N>(self, errno: u16, filename: N, location: Coordinate) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+    {
+        self.expect(Severity::Error, errno, filename, location)
+    }
+
+    /// Expect a warning at a given location.
+    pub fn expect_warning<N>(self, errno: u16, filename: N, location: Coordinate) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+    {
+        self.expect(Severity::Warning, errno, filename, location)
+    }
+
+    /// Expect a marker of some severity at a given location.
+    pub fn expect<N>(
+        mut self,
+        severity: Severity,
+        errno: u16,
+        filename: N,
+        location: Coordinate,
+    ) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+    {
+        if let Some(filename) = self.path(filename) {
+            self.frame.markers.push(Marker {
+                severity,
+                errno,
+                filename,
+                location,
+            });
+        }
+        self
+    }
+
+    /// Construct the path for a given filename.  If this fails, the
+    /// error is reported when the test file is built.
+    fn path<N: Into<Cow<'a, str>>>(&mut self, filename: N) -> Option<TestPath<'a>> {
+        match TestPath::new(filename) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                self.error.get_or_insert(BuildError::InvalidFilename(e));
+                None
+            }
+        }
+    }
+}
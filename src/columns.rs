@@ -0,0 +1,231 @@
+use crate::options::{OptionError, OptionErrorKind, OptionResult};
+use crate::{Config, Coordinate, Range, Snapshot, Value};
+use std::fmt;
+use std::ops;
+
+/// The configuration option declaring the unit of columns.
+pub const COLUMN_UNIT: &str = "column.unit";
+
+/// The configuration option declaring the width of a tab stop.
+pub const COLUMN_TAB_WIDTH: &str = "column.tab_width";
+
+// ===============================================================
+// Column Unit
+// ===============================================================
+
+/// Identifies what a single column of a line counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Each byte of UTF-8 counts as a column.
+    Byte,
+    /// Each character (i.e. Unicode scalar value) counts as a column.
+    #[default]
+    Char,
+    /// Each UTF-16 code unit counts as a column, such that characters
+    /// outside the basic multilingual plane take two columns.
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// Get the name of this unit, as used in a test file (e.g.
+    /// `"utf16"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnUnit::Byte => "byte",
+            ColumnUnit::Char => "char",
+            ColumnUnit::Utf16 => "utf16",
+        }
+    }
+
+    /// Determine the unit with a given name (if any).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "byte" => Some(ColumnUnit::Byte),
+            "char" => Some(ColumnUnit::Char),
+            "utf16" => Some(ColumnUnit::Utf16),
+            _ => None,
+        }
+    }
+
+    /// Determine the number of columns taken by a given character.
+    fn width(&self, c: char) -> usize {
+        match self {
+            ColumnUnit::Byte => c.len_utf8(),
+            ColumnUnit::Char => 1,
+            ColumnUnit::Utf16 => c.len_utf16(),
+        }
+    }
+}
+
+impl fmt::Display for ColumnUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// ===============================================================
+// Columns
+// ===============================================================
+
+/// Determines the meaning of the columns given by the coordinates of
+/// a test file.  Columns are numbered from `0` and count some unit of
+/// a line (e.g. characters), except that a tab advances to the next
+/// multiple of the tab width.  A test file declares these using the
+/// `column.unit` option (one of `"byte"`, `"char"` or `"utf16"`) and
+/// the `column.tab_width` option.  By default, columns count
+/// characters and a tab is a single column.
+///
+/// ```
+/// use whiley_test_file::{ColumnUnit, Columns};
+///
+/// let columns = Columns::new(ColumnUnit::Utf16, 4);
+/// let line = "\tx = \"😀\";";
+/// assert_eq!(columns.width(line), 13);
+/// assert_eq!(columns.offset(line, 4), Some(1));
+/// assert_eq!(columns.offset(line, 2), None);
+/// assert_eq!(columns.column(line, 10), Some(11));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Columns {
+    /// What a single column counts.
+    pub unit: ColumnUnit,
+    /// The distance between tab stops (where `1` means a tab is a
+    /// single column).
+    pub tab_width: usize,
+}
+
+impl Columns {
+    /// Construct column semantics for a given unit and tab width.
+    pub fn new(unit: ColumnUnit, tab_width: usize) -> Self {
+        assert!(tab_width > 0, "invalid tab width");
+        Columns { unit, tab_width }
+    }
+
+    /// Determine the column semantics declared by a given
+    /// configuration.
+    pub(crate) fn from_config(config: &Config) -> OptionResult<Self> {
+        let error = |key: &str| OptionError {
+            kind: OptionErrorKind::InvalidValue,
+            key: key.to_string(),
+        };
+        let mut columns = Columns::default();
+        match config.get(COLUMN_UNIT) {
+            Some(Value::String(s)) => {
+                columns.unit = ColumnUnit::from_name(s).ok_or_else(|| error(COLUMN_UNIT))?;
+            }
+            Some(_) => return Err(error(COLUMN_UNIT)),
+            None => {}
+        }
+        match config.get(COLUMN_TAB_WIDTH) {
+            Some(&Value::Int(i)) if i > 0 => {
+                columns.tab_width = usize::try_from(i).map_err(|_| error(COLUMN_TAB_WIDTH))?;
+            }
+            Some(_) => return Err(error(COLUMN_TAB_WIDTH)),
+            None => {}
+        }
+        Ok(columns)
+    }
+
+    /// Determine the number of columns taken by a given line.
+    pub fn width(&self, line: &str) -> usize {
+        line.chars().fold(0, |col, c| self.advance(col, c))
+    }
+
+    /// Determine the byte offset of a given column within a line.  A
+    /// column just past the end of the line gives its length, whilst
+    /// one beyond that (or within a character or tab) gives `None`.
+    pub fn offset(&self, line: &str, column: usize) -> Option<usize> {
+        let mut col = 0;
+        for (i, c) in line.char_indices() {
+            if col == column {
+                return Some(i);
+            } else if col > column {
+                return None;
+            }
+            col = self.advance(col, c);
+        }
+        (col == column).then_some(line.len())
+    }
+
+    /// Determine the column of a given byte offset within a line.  An
+    /// offset beyond the end of the line (or within a character)
+    /// gives `None`.
+    pub fn column(&self, line: &str, offset: usize) -> Option<usize> {
+        line.get(..offset).map(|prefix| self.width(prefix))
+    }
+
+    /// Determine the byte offsets within its line of the (inclusive)
+    /// range of columns identified by a coordinate, against the
+    /// contents of a given file in a snapshot.  For example, columns
+    /// `7:10` of `method main():` give the offsets `7..11`.  This
+    /// gives `None` if the file does not exist, or the coordinate is
+    /// not within it.
+    pub fn to_offsets(
+        &self,
+        snapshot: &Snapshot,
+        filename: &str,
+        location: Coordinate,
+    ) -> Option<ops::Range<usize>> {
+        let line = get_line(snapshot, filename, location.0)?;
+        let Range(i, j) = location.1;
+        if i > j {
+            return None;
+        }
+        let start = self.offset(line, i)?;
+        let end = self.offset(line, j)?;
+        // Include the character at the end column (if any)
+        let end = line[end..]
+            .chars()
+            .next()
+            .map_or(end, |c| end + c.len_utf8());
+        Some(start..end)
+    }
+
+    /// Determine the coordinate identifying the given byte offsets
+    /// within a line (numbered from `1`), against the contents of a
+    /// given file in a snapshot.  This is the inverse of
+    /// [`to_offsets()`](Self::to_offsets), where empty offsets give a
+    /// single column.
+    pub fn to_coordinate(
+        &self,
+        snapshot: &Snapshot,
+        filename: &str,
+        line: usize,
+        offsets: ops::Range<usize>,
+    ) -> Option<Coordinate> {
+        let text = get_line(snapshot, filename, line)?;
+        let start = offsets.start;
+        let i = self.column(text, start)?;
+        let j = match text.get(offsets)?.char_indices().next_back() {
+            Some((k, _)) => self.column(text, start + k)?,
+            None => i,
+        };
+        Some(Coordinate(line, Range(i, j)))
+    }
+
+    /// Determine the column following a given character.
+    fn advance(&self, column: usize, c: char) -> usize {
+        if c == '\t' && self.tab_width > 1 {
+            (column / self.tab_width + 1) * self.tab_width
+        } else {
+            column + self.unit.width(c)
+        }
+    }
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Columns {
+            unit: ColumnUnit::Char,
+            tab_width: 1,
+        }
+    }
+}
+
+/// Get the text of a given line (numbered from `1`) of a file in a
+/// snapshot, excluding any `\r` which terminates it.
+fn get_line<'a>(snapshot: &Snapshot<'a>, filename: &str, line: usize) -> Option<&'a str> {
+    let lines = snapshot.get(filename)?;
+    let text = *lines.get(line.checked_sub(1)?)?;
+    Some(text.strip_suffix('\r').unwrap_or(text))
+}
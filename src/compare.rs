@@ -0,0 +1,208 @@
+use crate::Marker;
+use std::fmt;
+
+// ===============================================================
+// Near Miss
+// ===============================================================
+
+/// Identifies an expected marker which was not produced, but for
+/// which a similar marker was.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum NearMiss<'a> {
+    /// A marker with the same code was produced in the same file, but
+    /// at a different location.
+    WrongLocation {
+        expected: Marker<'a>,
+        actual: Marker<'a>,
+    },
+    /// A marker was produced at the same location, but with a
+    /// different code.
+    WrongCode {
+        expected: Marker<'a>,
+        actual: Marker<'a>,
+    },
+}
+
+impl<'a> NearMiss<'a> {
+    /// Convert this near miss into one which owns all of its strings.
+    pub fn into_owned(self) -> NearMiss<'static> {
+        match self {
+            NearMiss::WrongLocation { expected, actual } => NearMiss::WrongLocation {
+                expected: expected.into_owned(),
+                actual: actual.into_owned(),
+            },
+            NearMiss::WrongCode { expected, actual } => NearMiss::WrongCode {
+                expected: expected.into_owned(),
+                actual: actual.into_owned(),
+            },
+        }
+    }
+}
+
+impl<'a> fmt::Display for NearMiss<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NearMiss::WrongLocation { expected, actual } => {
+                write!(f, "~ {} -> {} (wrong location)", expected, actual)
+            }
+            NearMiss::WrongCode { expected, actual } => {
+                write!(f, "~ {} -> {} (wrong code)", expected, actual)
+            }
+        }
+    }
+}
+
+// ===============================================================
+// Comparison
+// ===============================================================
+
+/// The result of comparing the markers expected for a frame against
+/// those actually produced.  Markers are compared as multisets (i.e.
+/// order does not matter, but duplicates do).  This is printed as a
+/// diff, with one marker per line:
+///
+/// ```text
+/// - E101 main.whiley 1,2 (missing)
+/// + E102 main.whiley 3,4 (unexpected)
+/// ~ E103 main.whiley 1,2 -> E103 main.whiley 2,2 (wrong location)
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Comparison<'a> {
+    /// Expected markers which were produced exactly.
+    pub matched: Vec<Marker<'a>>,
+    /// Expected markers which were not produced.
+    pub missing: Vec<Marker<'a>>,
+    /// Markers produced which were not expected.
+    pub unexpected: Vec<Marker<'a>>,
+    /// Expected markers for which a similar marker was produced.
+    pub near_misses: Vec<NearMiss<'a>>,
+}
+
+impl<'a> Comparison<'a> {
+    /// Convert this comparison into one which owns all of its strings.
+    pub fn into_owned(self) -> Comparison<'static> {
+        let owned = |ms: Vec<Marker>| ms.into_iter().map(Marker::into_owned).collect();
+        Comparison {
+            matched: owned(self.matched),
+            missing: owned(self.missing),
+            unexpected: owned(self.unexpected),
+            near_misses: self
+                .near_misses
+                .into_iter()
+                .map(NearMiss::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Check whether the expected and actual markers were identical.
+    pub fn is_match(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.near_misses.is_empty()
+    }
+}
+
+impl<'a> fmt::Display for Comparison<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for m in &self.missing {
+            writeln!(f, "- {} (missing)", m)?;
+        }
+        for m in &self.unexpected {
+            writeln!(f, "+ {} (unexpected)", m)?;
+        }
+        for n in &self.near_misses {
+            writeln!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the markers expected for a frame against those actually
+/// produced (e.g. by a compiler).  Exact matches are found first.
+/// Then, remaining markers at the same location are paired as having
+/// the wrong code, after which remaining markers with the same code
+/// in the same file are paired (nearest line first) as having the
+/// wrong location.  Anything left over is either missing or
+/// unexpected.
+///
+/// ```
+/// use whiley_test_file::{compare, WhileyTestFile};
+///
+/// let expected = WhileyTestFile::new("====\n---\nE101 main.whiley 1,2\nE102 main.whiley 3,0").unwrap();
+/// let actual = WhileyTestFile::new("====\n---\nE102 main.whiley 4,0").unwrap();
+/// let cmp = compare(&expected.frame(0).markers, &actual.frame(0).markers);
+/// assert_eq!(cmp.missing.len(), 1);
+/// assert_eq!(cmp.near_misses.len(), 1);
+/// ```
+pub fn compare<'a>(expected: &[Marker<'a>], actual: &[Marker<'a>]) -> Comparison<'a> {
+    let mut cmp = Comparison::default();
+    let mut expected: Vec<&Marker<'a>> = expected.iter().collect();
+    let mut actual: Vec<Option<&Marker<'a>>> = actual.iter().map(Some).collect();
+    // Exact matches
+    expected.retain(|e| match take(&mut actual, |a| a == *e) {
+        Some(_) => {
+            cmp.matched.push((*e).clone());
+            false
+        }
+        None => true,
+    });
+    // Same location, different code
+    expected.retain(|e| match take(&mut actual, |a| same_location(a, e)) {
+        Some(a) => {
+            cmp.near_misses.push(NearMiss::WrongCode {
+                expected: (*e).clone(),
+                actual: a.clone(),
+            });
+            false
+        }
+        None => true,
+    });
+    // Same code, different location
+    expected.retain(|e| {
+        let nearest = actual
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| a.filter(|a| same_code(a, e)).map(|a| (i, a)))
+            .min_by_key(|(_, a)| a.location.0.abs_diff(e.location.0))
+            .map(|(i, _)| i);
+        match nearest.and_then(|i| actual[i].take()) {
+            Some(a) => {
+                cmp.near_misses.push(NearMiss::WrongLocation {
+                    expected: (*e).clone(),
+                    actual: a.clone(),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+    // Everything else
+    cmp.missing = expected.into_iter().cloned().collect();
+    cmp.unexpected = actual.into_iter().flatten().cloned().collect();
+    cmp
+}
+
+/// Remove the first marker satisfying a given predicate (if any).
+fn take<'m, 'a, P>(markers: &mut [Option<&'m Marker<'a>>], pred: P) -> Option<&'m Marker<'a>>
+where
+    P: Fn(&Marker<'a>) -> bool,
+{
+    markers
+        .iter_mut()
+        .find(|m| matches!(m, Some(m) if pred(m)))
+        .and_then(|m| m.take())
+}
+
+/// Determine whether two markers are at the same location.
+fn same_location(a: &Marker, b: &Marker) -> bool {
+    a.filename == b.filename && a.location == b.location
+}
+
+/// Determine whether two markers have the same code and file.
+fn same_code(a: &Marker, b: &Marker) -> bool {
+    a.filename == b.filename && a.severity == b.severity && a.errno == b.errno
+}
@@ -0,0 +1,288 @@
+//! Parsers for turning the diagnostics reported by a compiler into
+//! [`Marker`]s, so they can be compared against those expected by a
+//! test.  Different output formats are supported by implementing
+//! [`DiagnosticParser`].  The textual output of the Whiley compiler
+//! (`wyc`) is handled by [`TextParser`], which expects each
+//! diagnostic to look like this:
+//!
+//! ```text
+//! main.whiley:3: expected type
+//!     x = y + 1
+//!         ^^^^^
+//! ```
+//!
+//! That is, a header line giving the filename, line number and
+//! message, followed by the offending source line and a line of
+//! carets identifying the (inclusive) range of columns concerned.
+//! Since the textual output does not include error codes, these are
+//! determined from the message.  When the `json` feature is enabled,
+//! the JSON output of the compiler is handled by `JsonParser`.
+use crate::{Coordinate, Marker, Range, Severity, TestPath};
+use std::error;
+use std::fmt;
+use std::result;
+
+// ===============================================================
+// Error
+// ===============================================================
+
+/// Identifies the kind of problem encountered when parsing the
+/// diagnostics reported by a compiler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagnosticErrorKind {
+    /// A diagnostic without a caret line identifying its columns.
+    MissingCaret,
+    /// A diagnostic whose message has no known error code.
+    UnknownMessage,
+    /// Output which is not valid JSON, or has an unexpected shape.
+    InvalidJson,
+    /// A diagnostic whose filename is not a relative path within the
+    /// project (see [`TestPath`]).
+    InvalidFilename,
+}
+
+impl DiagnosticErrorKind {
+    /// Get a short description of this kind of error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            DiagnosticErrorKind::MissingCaret => "missing caret line",
+            DiagnosticErrorKind::UnknownMessage => "unknown message",
+            DiagnosticErrorKind::InvalidJson => "invalid JSON",
+            DiagnosticErrorKind::InvalidFilename => "invalid filename",
+        }
+    }
+}
+
+/// Represents a problem encountered when parsing the diagnostics
+/// reported by a compiler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticError {
+    /// Identifies what kind of problem this is.
+    pub kind: DiagnosticErrorKind,
+    /// Line of output on which the problem arose (starting from `1`),
+    /// or `0` when this is not known.
+    pub line: usize,
+    /// Raw text of the offending line (or a description of the
+    /// problem for JSON output).
+    pub text: String,
+}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} `{}`",
+            self.line,
+            self.kind.message(),
+            self.text
+        )
+    }
+}
+
+impl error::Error for DiagnosticError {}
+
+pub type DiagnosticResult<T> = result::Result<T, DiagnosticError>;
+
+// ===============================================================
+// Diagnostic Parser
+// ===============================================================
+
+/// A parser for the diagnostics reported by some compiler.
+pub trait DiagnosticParser {
+    /// Parse the output of a compiler into markers, in order of
+    /// appearance.
+    fn parse<'a>(&self, output: &'a str) -> DiagnosticResult<Vec<Marker<'a>>>;
+}
+
+impl<P: DiagnosticParser + ?Sized> DiagnosticParser for Box<P> {
+    fn parse<'a>(&self, output: &'a str) -> DiagnosticResult<Vec<Marker<'a>>> {
+        (**self).parse(output)
+    }
+}
+
+// ===============================================================
+// Text Parser
+// ===============================================================
+
+/// A parser for the textual output of the Whiley compiler.  Error
+/// codes are determined by matching the start of each message against
+/// a table of known messages (with the longest match taking
+/// precedence).  A message beginning `warning: ` gives a warning,
+/// otherwise an error is assumed.  Lines which are not part of a
+/// diagnostic are ignored.
+///
+/// ```
+/// use whiley_test_file::diagnostics::{DiagnosticParser, TextParser};
+///
+/// let parser = TextParser::new().message("expected type", 301);
+/// let output = "main.whiley:1: expected type\nfunction f(x) -> int:\n           ^\n";
+/// let markers = parser.parse(output).unwrap();
+/// assert_eq!(markers[0].to_string(), "E301 main.whiley 1,11");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TextParser {
+    messages: Vec<(String, u16)>,
+}
+
+impl TextParser {
+    /// Construct a text parser which knows no messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associate messages beginning with the given text with an error
+    /// code.
+    pub fn message<S: Into<String>>(mut self, prefix: S, errno: u16) -> Self {
+        self.messages.push((prefix.into(), errno));
+        self
+    }
+
+    /// Determine the error code for a given message (if known).
+    fn errno(&self, message: &str) -> Option<u16> {
+        self.messages
+            .iter()
+            .filter(|(p, _)| message.starts_with(p.as_str()))
+            .max_by_key(|(p, _)| p.len())
+            .map(|(_, e)| *e)
+    }
+}
+
+impl DiagnosticParser for TextParser {
+    fn parse<'a>(&self, output: &'a str) -> DiagnosticResult<Vec<Marker<'a>>> {
+        let lines: Vec<&str> = output.lines().collect();
+        let mut markers = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let (filename, number, message) = match parse_header(line) {
+                Some(h) => h,
+                None => continue,
+            };
+            let error = |kind| DiagnosticError {
+                kind,
+                line: i + 1,
+                text: line.to_string(),
+            };
+            let (severity, message) = match message.strip_prefix("warning: ") {
+                Some(m) => (Severity::Warning, m),
+                None => (Severity::Error, message.trim_start_matches("error: ")),
+            };
+            let filename =
+                TestPath::new(filename).map_err(|_| error(DiagnosticErrorKind::InvalidFilename))?;
+            let errno = self
+                .errno(message)
+                .ok_or_else(|| error(DiagnosticErrorKind::UnknownMessage))?;
+            let range = lines
+                .get(i + 2)
+                .and_then(|l| parse_caret(l))
+                .ok_or_else(|| error(DiagnosticErrorKind::MissingCaret))?;
+            markers.push(Marker {
+                severity,
+                errno,
+                filename,
+                location: Coordinate(number, range),
+            });
+        }
+        Ok(markers)
+    }
+}
+
+/// Parse the header line of a diagnostic, such as:
+///
+/// ```text
+/// main.whiley:3: expected type
+/// ```
+fn parse_header(line: &str) -> Option<(&str, usize, &str)> {
+    let (filename, rest) = line.split_once(':')?;
+    let (number, message) = rest.split_once(": ")?;
+    if filename.is_empty() || filename.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((filename, number.parse().ok()?, message))
+}
+
+/// Parse a line of carets identifying a range of columns, such as:
+///
+/// ```text
+///     ^^^^^
+/// ```
+fn parse_caret(line: &str) -> Option<Range> {
+    let line = line.trim_end();
+    let start = line.find('^')?;
+    let carets = &line[start..];
+    if !line[..start].trim().is_empty() || carets.contains(|c| c != '^') {
+        return None;
+    }
+    let start = line[..start].chars().count();
+    Some(Range(start, start + carets.len() - 1))
+}
+
+// ===============================================================
+// JSON Parser
+// ===============================================================
+
+/// A parser for the JSON output of the Whiley compiler.  This expects
+/// an array of diagnostics, each of the form:
+///
+/// ```text
+/// { "file": string, "line": integer, "start": integer, "end": integer,
+///   "code": integer, "severity": "error" | "warning" | "info" }
+/// ```
+///
+/// where `severity` is optional (defaulting to `"error"`), and any
+/// other fields (e.g. `"message"`) are ignored.  Any other severity is
+/// reported as invalid.
+#[cfg(feature = "json")]
+#[derive(Clone, Debug, Default)]
+pub struct JsonParser;
+
+#[cfg(feature = "json")]
+#[derive(serde::Deserialize)]
+struct JsonDiagnostic<'a> {
+    #[serde(borrow)]
+    file: std::borrow::Cow<'a, str>,
+    line: usize,
+    start: usize,
+    end: usize,
+    code: u16,
+    #[serde(borrow, default)]
+    severity: Option<std::borrow::Cow<'a, str>>,
+}
+
+#[cfg(feature = "json")]
+impl DiagnosticParser for JsonParser {
+    fn parse<'a>(&self, output: &'a str) -> DiagnosticResult<Vec<Marker<'a>>> {
+        let diagnostics: Vec<JsonDiagnostic<'a>> =
+            serde_json::from_str(output).map_err(|e| DiagnosticError {
+                kind: DiagnosticErrorKind::InvalidJson,
+                line: e.line(),
+                text: e.to_string(),
+            })?;
+        diagnostics
+            .into_iter()
+            .map(|d| {
+                let filename = TestPath::new(d.file).map_err(|e| DiagnosticError {
+                    kind: DiagnosticErrorKind::InvalidFilename,
+                    line: 0,
+                    text: e.path,
+                })?;
+                let severity = match d.severity.as_deref() {
+                    None | Some("error") => Severity::Error,
+                    Some("warning") => Severity::Warning,
+                    Some("info") => Severity::Info,
+                    Some(s) => {
+                        return Err(DiagnosticError {
+                            kind: DiagnosticErrorKind::InvalidJson,
+                            line: 0,
+                            text: format!("unknown severity \"{}\"", s),
+                        });
+                    }
+                };
+                Ok(Marker {
+                    severity,
+                    errno: d.code,
+                    filename,
+                    location: Coordinate(d.line, Range(d.start, d.end)),
+                })
+            })
+            .collect()
+    }
+}
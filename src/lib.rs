@@ -31,6 +31,48 @@
 //! updates the contents of `main.whiley`.  Furthermore, compiling the
 //! snapshot at this point is not expected to produce any errors.
 //!
+//! The range of an insert action (e.g. `1:1`) identifies the lines it
+//! replaces, which are numbered from `1` and inclusive.  Thus, `3` (or
+//! `3:3`) replaces only the third line.  The empty range `i:i-1`
+//! replaces no lines and inserts before line `i`, such that `1:0`
+//! inserts at the start of a file (including an empty one) and `n+1:n`
+//! appends to a file of `n` lines.
+//!
+//! A line of file content which begins with a prefix (i.e. `===`,
+//! `---`, `>>>` or `<<<`) is escaped with a backslash (e.g. `\---`),
+//! since it would otherwise be read as a control line.  The backslash
+//! is removed when parsing, and only lines which need it are escaped
+//! when printing (including those like `\---` which already begin
+//! with an escape).
+//!
+//! Fields of action headers and markers are separated by any amount of
+//! whitespace.  A filename containing whitespace is quoted (e.g.
+//! `"my file.whiley"`), with `\"` giving a quote within it.
+//!
+//! File content is recorded exactly, including its line terminators.
+//! A content line ending in `\r\n` keeps its `\r`, and the line
+//! `\ No newline at end of file` (as used by `diff`) indicates that
+//! the line before it has no terminator at all.  For example, the
+//! following gives `main.whiley` the contents `"skip"`:
+//!
+//! ```text
+//! ================
+//! >>> main.whiley
+//! skip
+//! \ No newline at end of file
+//! ```
+//!
+//! By default, the columns of a marker count characters.  A test file
+//! can instead declare that they count bytes or UTF-16 code units with
+//! `column.unit = "byte"` or `column.unit = "utf16"`, and that tabs
+//! advance to the next tab stop with (e.g.) `column.tab_width = 4`.
+//!
+//! Filenames are paths relative to the root of the project, whose
+//! components are separated by either `/` or `\`.  They are normalised
+//! when parsed (e.g. `./main.whiley`, `src/../main.whiley` and
+//! `src\..\main.whiley` are all `main.whiley`).  Absolute paths, and
+//! those which escape the project root, are rejected.
+//!
 //! ```
 //! use std::fs;
 //! use whiley_test_file::WhileyTestFile;
@@ -44,19 +86,50 @@
 //! }
 //! ```
 
+// Public modules
+pub mod diagnostics;
+pub mod options;
+pub mod report;
+pub mod runner;
+pub mod syntax;
+
 // Hidden modules
+mod builder;
+mod columns;
+mod compare;
+mod materialise;
 mod parser;
+mod path;
+mod printer;
+#[cfg(feature = "serde")]
+mod serialise;
+mod snapshot;
+mod validate;
 
+pub use builder::{BuildError, BuildResult, FrameBuilder, WhileyTestFileBuilder};
+pub use columns::{ColumnUnit, Columns, COLUMN_TAB_WIDTH, COLUMN_UNIT};
+pub use compare::{compare, Comparison, NearMiss};
+pub use materialise::{Change, Materialiser};
 use parser::Parser;
+pub use path::{PathError, PathErrorKind, PathResult, TestPath};
+pub use snapshot::{Snapshot, SnapshotError, SnapshotResult, Snapshots};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops;
 use std::result;
+pub use validate::{Item, Violation, ViolationKind};
 
 // ===============================================================
 // Error
 // ===============================================================
 
+/// Identifies the kind of problem encountered when parsing a test
+/// file.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Error {
+pub enum ErrorKind {
     UnexpectedEof,
     InvalidConfigOption,
     InvalidConfigValue,
@@ -67,15 +140,113 @@ pub enum Error {
     InvalidMarker,
     InvalidErrorCode,
     InvalidCoordinate,
+    InvalidFilename,
+    UnexpectedNoNewline,
+    UnexpectedLine,
+}
+
+impl ErrorKind {
+    /// Get a short description of this kind of error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedEof => "unexpected end-of-file",
+            ErrorKind::InvalidConfigOption => "invalid configuration option",
+            ErrorKind::InvalidConfigValue => "invalid configuration value",
+            ErrorKind::InvalidIntValue => "invalid integer value",
+            ErrorKind::InvalidStringValue => "invalid string value",
+            ErrorKind::InvalidAction => "invalid action",
+            ErrorKind::InvalidRange => "invalid range",
+            ErrorKind::InvalidMarker => "invalid marker",
+            ErrorKind::InvalidErrorCode => "invalid error code",
+            ErrorKind::InvalidCoordinate => "invalid coordinate",
+            ErrorKind::InvalidFilename => "invalid filename",
+            ErrorKind::UnexpectedNoNewline => "unexpected no newline marker",
+            ErrorKind::UnexpectedLine => "unexpected line",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Represents a problem encountered when parsing a test file.  This
+/// identifies the line on which the problem arose, along with the
+/// offending text within that line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// Identifies what kind of problem this is.
+    pub kind: ErrorKind,
+    /// Line on which the problem arose (starting from `1`).
+    pub line: usize,
+    /// Byte offsets within the line of the offending text.
+    pub span: ops::Range<usize>,
+    /// Raw text of the offending line.
+    pub text: String,
+    /// Optional hint as to what was expected.
+    pub hint: Option<&'static str>,
+}
+
+impl Error {
+    /// Get the offending text within the line.
+    pub fn token(&self) -> &str {
+        &self.text[self.span.clone()]
+    }
+
+    /// Get the column (starting from `1`) at which the offending text
+    /// begins, counting characters rather than bytes.
+    pub fn column(&self) -> usize {
+        self.text[..self.span.start].chars().count() + 1
+    }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line,
+            self.column(),
+            self.kind
+        )?;
+        if !self.span.is_empty() {
+            write!(f, " `{}`", self.token())?;
+        }
+        if let Some(hint) = self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Error {}
+
 pub type Result<T> = result::Result<T, Error>;
 
 // ===============================================================
 // Test File
 // ===============================================================
 
+/// Represents a parsed test file.  Strings within a test file are
+/// either borrowed from the input it was parsed from, or owned.  An
+/// owned test file (i.e. a [`WhileyTestFileBuf`]) no longer depends
+/// on its input, and so can be stored or sent across threads freely.
+///
+/// ```
+/// use whiley_test_file::{WhileyTestFile, WhileyTestFileBuf};
+///
+/// let owned: WhileyTestFileBuf = {
+///     let input = String::from("x = 1\n====\n>>> main.whiley\n");
+///     WhileyTestFile::new(&input).unwrap().into_owned()
+/// };
+/// assert_eq!(owned.get_int("x"), Some(1));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhileyTestFile<'a> {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialise::sorted"))]
     config: Config<'a>,
     frames: Vec<Frame<'a>>,
 }
@@ -90,21 +261,104 @@ impl<'a> WhileyTestFile<'a> {
         Ok(wtf)
     }
 
+    /// Parse a test file whilst recovering from errors, rather than
+    /// stopping at the first.  This returns as much of the test file
+    /// as could be parsed, along with every error encountered.  The
+    /// test file is only valid when no errors are returned.
+    pub fn new_recovering(input: &'a str) -> (WhileyTestFile<'a>, Vec<Error>) {
+        // Construct parser
+        let mut parser = Parser::new(input);
+        // Parse file (recording errors)
+        parser.parse_recovering()
+    }
+
+    /// Convert this test file into one which owns all of its strings
+    /// (i.e. which no longer borrows from its input).
+    pub fn into_owned(self) -> WhileyTestFileBuf {
+        let config = self
+            .config
+            .into_iter()
+            .map(|(k, v)| (owned(k), v.into_owned()))
+            .collect();
+        let frames = self.frames.into_iter().map(Frame::into_owned).collect();
+        WhileyTestFile { config, frames }
+    }
+
+    /// Construct a copy of this test file which owns all of its
+    /// strings (i.e. which no longer borrows from its input).
+    pub fn to_owned(&self) -> WhileyTestFileBuf {
+        self.clone().into_owned()
+    }
+
     /// Get configuration option associated with the given key.
-    pub fn get(&self, key: &str) -> Option<&Value> {
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
         self.config.get(key)
     }
 
+    /// Iterate the configuration options of this test file (in no
+    /// particular order).
+    pub fn config(&self) -> impl Iterator<Item = (&str, &Value<'a>)> {
+        self.config.iter().map(|(k, v)| (k.as_ref(), v))
+    }
+
     /// Get number of frames in this test file.
     pub fn size(&self) -> usize {
         self.frames.len()
     }
 
     /// Get nth frame within this test file.
-    pub fn frame(&self, n: usize) -> &Frame {
+    pub fn frame(&self, n: usize) -> &Frame<'a> {
         &self.frames[n]
     }
 
+    /// Get nth frame within this test file, allowing it to be
+    /// modified (e.g. to replace its markers).
+    pub fn frame_mut(&mut self, n: usize) -> &mut Frame<'a> {
+        &mut self.frames[n]
+    }
+
+    /// Get the frames of this test file, allowing them to be modified
+    /// or reordered.
+    pub fn frames_mut(&mut self) -> &mut [Frame<'a>] {
+        &mut self.frames
+    }
+
+    /// Insert a frame at position `n` within this test file, shifting
+    /// all frames after it along.
+    pub fn insert_frame(&mut self, n: usize, frame: Frame<'a>) {
+        self.frames.insert(n, frame);
+    }
+
+    /// Remove and return the nth frame of this test file, shifting all
+    /// frames after it back.
+    pub fn remove_frame(&mut self, n: usize) -> Frame<'a> {
+        self.frames.remove(n)
+    }
+
+    /// Split the nth frame of this test file into two frames, such
+    /// that the first contains its actions before index `at`, and the
+    /// second the remainder.  The markers move to the second frame,
+    /// since they describe the state after all actions are applied.
+    pub fn split_frame(&mut self, n: usize, at: usize) {
+        let frame = &mut self.frames[n];
+        let second = Frame {
+            actions: frame.actions.split_off(at),
+            markers: std::mem::take(&mut frame.markers),
+        };
+        self.frames.insert(n + 1, second);
+    }
+
+    /// Merge the nth frame of this test file with the one after it,
+    /// such that the merged frame contains the actions of both (in
+    /// order).  The markers of the nth frame are discarded, since
+    /// they describe a state which no longer arises.
+    pub fn merge_frames(&mut self, n: usize) {
+        let second = self.frames.remove(n + 1);
+        let frame = &mut self.frames[n];
+        frame.actions.extend(second.actions);
+        frame.markers = second.markers;
+    }
+
     /// Get configuration option which is expected to be an integer.
     /// If its not an integer, or no such key exists, `None` is
     /// returned.
@@ -127,30 +381,163 @@ impl<'a> WhileyTestFile<'a> {
 
     /// Get configuration option which is expected to be a string If
     /// its not a string, or no such key exists, `None` is returned.
-    pub fn get_str(&self, key: &str) -> Option<&'a str> {
-        match &self.config.get(key) {
-            Some(&Value::String(s)) => Some(s),
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.config.get(key) {
+            Some(Value::String(s)) => Some(s),
             _ => None,
         }
     }
 
     /// Obtain an iterator to the frames of this test file.
-    pub fn iter<'b>(&'b self) -> std::slice::Iter<'b, Frame> {
+    pub fn iter<'b>(&'b self) -> std::slice::Iter<'b, Frame<'a>> {
         self.frames.iter()
     }
+
+    /// Obtain an iterator to the frames of this test file, allowing
+    /// them to be modified.
+    pub fn iter_mut<'b>(&'b mut self) -> std::slice::IterMut<'b, Frame<'a>> {
+        self.frames.iter_mut()
+    }
+
+    /// Obtain an iterator over the state of the project after each
+    /// frame of this test file (i.e. after frame `0`, then frame `1`,
+    /// etc).  Iteration stops after the first frame which cannot be
+    /// applied, yielding the error.
+    pub fn snapshots(&self) -> Snapshots<'_> {
+        Snapshots::new(&self.frames)
+    }
+
+    /// Check this test file for problems which parsing cannot detect,
+    /// returning every violation found.  Specifically, this checks
+    /// that every action can be applied, that `INSERT` actions on the
+    /// same file within a frame do not overlap, and that every marker
+    /// names a file in the snapshot after its frame and a location
+    /// within that file.  Marker lines are numbered from `1`, whilst
+    /// a marker range may extend upto (but not beyond) the width of
+    /// its line (excluding any trailing `\r`).  Widths are determined
+    /// by the columns declared by this test file (see
+    /// [`columns()`](Self::columns)).  If these are invalid, this is
+    /// reported and the default columns are used instead.
+    pub fn validate(&self) -> Vec<Violation> {
+        match self.columns() {
+            Ok(columns) => validate::validate(&self.frames, &columns),
+            Err(e) => {
+                let mut violations = vec![Violation {
+                    frame: 0,
+                    item: Item::Config,
+                    kind: ViolationKind::InvalidOption(e),
+                }];
+                violations.extend(validate::validate(&self.frames, &Columns::default()));
+                violations
+            }
+        }
+    }
+
+    /// Determine the meaning of the columns given by the coordinates
+    /// of this test file, as declared by its `column.unit` and
+    /// `column.tab_width` options (see [`Columns`]).  An error is
+    /// returned if either option has an invalid value.
+    ///
+    /// ```
+    /// use whiley_test_file::{ColumnUnit, WhileyTestFile};
+    ///
+    /// let wtf = WhileyTestFile::new("column.unit = \"utf16\"\ncolumn.tab_width = 4\n").unwrap();
+    /// let columns = wtf.columns().unwrap();
+    /// assert_eq!(columns.unit, ColumnUnit::Utf16);
+    /// assert_eq!(columns.tab_width, 4);
+    /// ```
+    pub fn columns(&self) -> options::OptionResult<Columns> {
+        Columns::from_config(&self.config)
+    }
+
+    /// Normalise the line terminators of every file in this test
+    /// file, such that every line is terminated by `\n`.  That is,
+    /// any `\r` ending a line is removed and every file ends with a
+    /// newline.
+    ///
+    /// ```
+    /// use whiley_test_file::WhileyTestFile;
+    ///
+    /// let mut wtf = WhileyTestFile::new("====\r\n>>> main.whiley\r\nskip\r\n\\ No newline at end of file").unwrap();
+    /// assert_eq!(wtf.frame(0).actions[0].lines(), ["skip\r"]);
+    /// wtf.normalise_line_endings();
+    /// assert_eq!(wtf.frame(0).actions[0].lines(), ["skip"]);
+    /// assert!(wtf.frame(0).actions[0].ends_with_newline());
+    /// ```
+    pub fn normalise_line_endings(&mut self) {
+        for frame in &mut self.frames {
+            for action in &mut frame.actions {
+                action.normalise_line_endings();
+            }
+        }
+    }
+
+    /// Write this test file in the format described by RFC#110 to a
+    /// given output stream.  Formatting to a string (or any other
+    /// `fmt::Write`) is supported via `Display`.
+    pub fn write_to<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
 }
 
+/// A test file which owns all of its strings.
+pub type WhileyTestFileBuf = WhileyTestFile<'static>;
+
 // ===============================================================
 // Config
 // ===============================================================
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Value<'a> {
-    String(&'a str),
+    String(Cow<'a, str>),
     Int(i64),
     Bool(bool),
 }
-type Config<'a> = HashMap<&'a str, Value<'a>>;
+
+impl<'a> Value<'a> {
+    /// Convert this value into one which owns its string (if any).
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::String(s) => Value::String(Cow::Owned(s.into_owned())),
+            Value::Int(i) => Value::Int(i),
+            Value::Bool(b) => Value::Bool(b),
+        }
+    }
+}
+
+impl<'a> From<bool> for Value<'a> {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl<'a> From<i64> for Value<'a> {
+    fn from(i: i64) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl<'a> From<i32> for Value<'a> {
+    fn from(i: i32) -> Self {
+        Value::Int(i.into())
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(s: &'a str) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl<'a> From<String> for Value<'a> {
+    fn from(s: String) -> Self {
+        Value::String(s.into())
+    }
+}
+
+type Config<'a> = HashMap<Cow<'a, str>, Value<'a>>;
 
 // ===============================================================
 // Frame
@@ -162,38 +549,115 @@ type Config<'a> = HashMap<&'a str, Value<'a>>;
 /// The set of actions includes _inserting_ and _removing_ lines on a
 /// specific file.  Actions are applied in the order of appearance,
 /// though they are not expected to overlap.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame<'a> {
     pub actions: Vec<Action<'a>>,
     pub markers: Vec<Marker<'a>>,
 }
 
+impl<'a> Frame<'a> {
+    /// Convert this frame into one which owns all of its strings.
+    pub fn into_owned(self) -> Frame<'static> {
+        Frame {
+            actions: self.actions.into_iter().map(Action::into_owned).collect(),
+            markers: self.markers.into_iter().map(Marker::into_owned).collect(),
+        }
+    }
+}
+
 // ===============================================================
 // Action
 // ===============================================================
 
 /// Represents an atomic action which can be applied to a source file,
 /// such as inserting or replacing lines within the file.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(from = "serialise::ActionRepr<'a>")
+)]
 pub enum Action<'a> {
-    CREATE(&'a str, Vec<&'a str>),
-    REMOVE(&'a str),
-    INSERT(&'a str, Range, Vec<&'a str>),
+    /// Set the contents of a file.  The flag indicates whether the
+    /// last line is terminated (i.e. whether the file ends with a
+    /// newline).
+    CREATE(TestPath<'a>, Vec<Cow<'a, str>>, bool),
+    /// Delete a file.
+    REMOVE(TestPath<'a>),
+    /// Replace lines `i` through `j` (inclusive, numbered from `1`)
+    /// of an existing file, where `i:i-1` inserts before line `i`
+    /// without replacing any.  The flag indicates whether the last line
+    /// inserted is terminated, and can only be cleared when `j` is
+    /// the last line of the file.
+    INSERT(TestPath<'a>, Range, Vec<Cow<'a, str>>, bool),
 }
 
 impl<'a> Action<'a> {
-    pub fn lines(&self) -> &[&'a str] {
+    /// Convert this action into one which owns all of its strings.
+    pub fn into_owned(self) -> Action<'static> {
+        match self {
+            Action::CREATE(f, lines, nl) => {
+                Action::CREATE(f.into_owned(), lines.into_iter().map(owned).collect(), nl)
+            }
+            Action::REMOVE(f) => Action::REMOVE(f.into_owned()),
+            Action::INSERT(f, r, lines, nl) => Action::INSERT(
+                f.into_owned(),
+                r,
+                lines.into_iter().map(owned).collect(),
+                nl,
+            ),
+        }
+    }
+
+    /// Get the filename to which this action applies.
+    pub fn filename(&self) -> &TestPath<'a> {
         match self {
-            Action::CREATE(_, lines) => lines,
-            Action::INSERT(_, _, lines) => lines,
+            Action::CREATE(f, _, _) | Action::REMOVE(f) | Action::INSERT(f, _, _, _) => f,
+        }
+    }
+
+    pub fn lines(&self) -> &[Cow<'a, str>] {
+        match self {
+            Action::CREATE(_, lines, _) => lines,
+            Action::INSERT(_, _, lines, _) => lines,
+            _ => {
+                panic!("no line information!");
+            }
+        }
+    }
+
+    /// Check whether the last line of this action is terminated.
+    pub fn ends_with_newline(&self) -> bool {
+        match self {
+            Action::CREATE(_, _, nl) | Action::INSERT(_, _, _, nl) => *nl,
             _ => {
                 panic!("no line information!");
             }
         }
     }
 
+    /// Normalise the line terminators of this action, such that every
+    /// line is terminated by `\n` (i.e. removing any trailing `\r`).
+    pub fn normalise_line_endings(&mut self) {
+        if let Action::CREATE(_, lines, nl) | Action::INSERT(_, _, lines, nl) = self {
+            for line in lines.iter_mut() {
+                match line {
+                    Cow::Borrowed(l) => *l = l.strip_suffix('\r').unwrap_or(l),
+                    Cow::Owned(l) => {
+                        if l.ends_with('\r') {
+                            l.pop();
+                        }
+                    }
+                }
+            }
+            *nl = true;
+        }
+    }
+
     pub fn range(&self) -> &Range {
         match self {
-            Action::INSERT(_, r, _) => r,
+            Action::INSERT(_, r, _, _) => r,
             _ => {
                 panic!("no range information!");
             }
@@ -206,18 +670,70 @@ impl<'a> Action<'a> {
 // ===============================================================
 
 /// Identifies an expected error at a location in a given source file.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Marker<'a> {
+    pub severity: Severity,
     pub errno: u16,
-    pub filename: &'a str,
+    pub filename: TestPath<'a>,
     pub location: Coordinate,
 }
 
+impl<'a> Marker<'a> {
+    /// Convert this marker into one which owns its filename.
+    pub fn into_owned(self) -> Marker<'static> {
+        Marker {
+            filename: self.filename.into_owned(),
+            ..self
+        }
+    }
+}
+
+/// Identifies the severity of a marker, as determined by the letter
+/// prefixing its code (e.g. `E101` is an error, whilst `W101` is a
+/// warning).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    /// Some other (alphabetic) letter.
+    Unknown(char),
+}
+
+impl Severity {
+    /// Determine the severity corresponding to a given letter.
+    pub fn from_char(c: char) -> Self {
+        match c {
+            'E' => Severity::Error,
+            'W' => Severity::Warning,
+            'I' => Severity::Info,
+            _ => Severity::Unknown(c),
+        }
+    }
+
+    /// Get the letter corresponding to this severity.
+    pub fn to_char(self) -> char {
+        match self {
+            Severity::Error => 'E',
+            Severity::Warning => 'W',
+            Severity::Info => 'I',
+            Severity::Unknown(c) => c,
+        }
+    }
+}
+
 // ===============================================================
 // Coordinate
 // ===============================================================
 
 /// Identifies a specific range of characters within a file.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "serialise::CoordinateRepr", into = "serialise::CoordinateRepr")
+)]
 pub struct Coordinate(pub usize, pub Range);
 
 // ===============================================================
@@ -226,4 +742,14 @@ pub struct Coordinate(pub usize, pub Range);
 
 /// Represents an interval (e.g. of characters within a line).
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Range(pub usize, pub usize);
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+/// Convert a string into one which is owned.
+fn owned(s: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
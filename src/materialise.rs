@@ -0,0 +1,130 @@
+use crate::Snapshot;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Identifies a change made to the target directory when writing a
+/// snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A file was written (i.e. created or its contents changed).
+    Write(String),
+    /// A file was removed.
+    Remove(String),
+}
+
+/// Responsible for writing snapshots of a project into a target
+/// directory.  The same directory can be reused for successive
+/// snapshots, in which case only files whose contents differ from
+/// those on disk are written, and files no longer present are
+/// removed.  Thus, a file watching compiler sees the same incremental
+/// edits as described by the test.  Files in the target directory
+/// which are not known to this materialiser are left untouched, and
+/// nothing is ever written outside it (since filenames cannot escape
+/// the project root).
+///
+/// ```no_run
+/// use whiley_test_file::{Materialiser, WhileyTestFile};
+///
+/// let wtf = WhileyTestFile::new("====\n>>> main.whiley\nmethod main()").unwrap();
+/// let mut target = Materialiser::new("build/test");
+/// for snapshot in wtf.snapshots() {
+///     target.write(&snapshot.unwrap()).unwrap();
+///     // compile ...
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Materialiser {
+    root: PathBuf,
+    // Files known to be in the target directory.
+    known: BTreeSet<String>,
+}
+
+impl Materialiser {
+    /// Construct a materialiser for a given target directory, which
+    /// is created if necessary when a snapshot is first written.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Materialiser {
+            root: root.into(),
+            known: BTreeSet::new(),
+        }
+    }
+
+    /// Get the target directory of this materialiser.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Record that the files of a given snapshot may be present in
+    /// the target directory, without writing anything.  This is useful
+    /// when resuming from a snapshot written previously (e.g. by a
+    /// different process), since any such file no longer present in
+    /// the next snapshot written will then be removed.
+    pub fn track(&mut self, snapshot: &Snapshot) {
+        for (filename, _) in snapshot.iter() {
+            self.known.insert(filename.to_string());
+        }
+    }
+
+    /// Write a given snapshot into the target directory, creating
+    /// subdirectories for nested filenames as necessary.  Likewise,
+    /// subdirectories left empty by removing a file are themselves
+    /// removed.  This returns the set of changes actually made, or an
+    /// error naming the path which could not be written or removed.
+    pub fn write(&mut self, snapshot: &Snapshot) -> io::Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        fs::create_dir_all(&self.root).map_err(|e| annotate(&self.root, e))?;
+        // Remove files which no longer exist
+        let removed: Vec<String> = self
+            .known
+            .iter()
+            .filter(|f| !snapshot.contains(f))
+            .cloned()
+            .collect();
+        for filename in removed {
+            self.known.remove(&filename);
+            let path = self.root.join(&filename);
+            match fs::remove_file(&path) {
+                Ok(()) => changes.push(Change::Remove(filename)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(annotate(&path, e)),
+            }
+            self.prune(&path);
+        }
+        // Write files which have changed
+        for (filename, _) in snapshot.iter() {
+            let path = self.root.join(filename);
+            let contents = snapshot.contents(filename).unwrap();
+            self.known.insert(filename.to_string());
+            if let Ok(existing) = fs::read_to_string(&path) {
+                if existing == contents {
+                    continue;
+                }
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| annotate(parent, e))?;
+            }
+            fs::write(&path, &contents).map_err(|e| annotate(&path, e))?;
+            changes.push(Change::Write(filename.to_string()));
+        }
+        Ok(changes)
+    }
+
+    /// Remove the directories containing a given path which are now
+    /// empty, stopping at the target directory.
+    fn prune(&self, path: &Path) {
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if d == self.root || fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+}
+
+/// Include the path concerned in an error.
+fn annotate(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,256 @@
+//! Declarative mappings from the configuration options of a test file
+//! to the command-line arguments of the tool which runs it.  Options
+//! are namespaced by tool (e.g. `boogie.timeout`), and each is given
+//! a type, a flag and (optionally) a default value.  For example:
+//!
+//! ```
+//! use whiley_test_file::options::Options;
+//! use whiley_test_file::WhileyTestFile;
+//!
+//! let options = Options::new()
+//!     .switch("whiley.verify", "--verify")
+//!     .int("boogie.timeout", "--timeout")
+//!     .with_default("boogie.timeout", 10);
+//! let wtf = WhileyTestFile::new("whiley.verify = true\n").unwrap();
+//! assert_eq!(options.args(&wtf).unwrap(), ["--verify", "--timeout", "10"]);
+//! ```
+//!
+//! Arguments are generated in the order options are declared, and any
+//! configuration option which is not declared (or explicitly ignored)
+//! is reported as an error.
+use crate::{Value, WhileyTestFile};
+use std::error;
+use std::fmt;
+use std::result;
+
+// ===============================================================
+// Error
+// ===============================================================
+
+/// Identifies the kind of problem encountered when mapping
+/// configuration options to command-line arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptionErrorKind {
+    /// A configuration option which has not been declared.
+    UnknownOption,
+    /// A configuration option whose value has the wrong type.
+    InvalidValue,
+}
+
+impl OptionErrorKind {
+    /// Get a short description of this kind of error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            OptionErrorKind::UnknownOption => "unknown option",
+            OptionErrorKind::InvalidValue => "invalid value for option",
+        }
+    }
+}
+
+/// Represents a problem encountered when mapping configuration
+/// options to command-line arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionError {
+    /// Identifies what kind of problem this is.
+    pub kind: OptionErrorKind,
+    /// The offending configuration option.
+    pub key: String,
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} `{}`", self.kind.message(), self.key)
+    }
+}
+
+impl error::Error for OptionError {}
+
+pub type OptionResult<T> = result::Result<T, OptionError>;
+
+// ===============================================================
+// Options
+// ===============================================================
+
+/// Identifies how a declared option is turned into arguments.
+#[derive(Clone, Debug, PartialEq)]
+enum Flag {
+    /// A boolean option passed as the flag when `true`, and omitted
+    /// otherwise.
+    Switch(String),
+    /// A boolean option passed as the flag when `false`, and omitted
+    /// otherwise.
+    Negated(String),
+    /// An integer option passed as the flag followed by its value.
+    Int(String),
+    /// A string option passed as the flag followed by its value.
+    String(String),
+}
+
+/// A declared option.
+#[derive(Clone, Debug, PartialEq)]
+struct Spec {
+    key: String,
+    flag: Flag,
+    default: Option<Value<'static>>,
+}
+
+/// A mapping from configuration options to command-line arguments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    specs: Vec<Spec>,
+    ignored: Vec<String>,
+}
+
+impl Options {
+    /// Construct a mapping which declares no options.
+    pub fn new() -> Self {
+        Options {
+            specs: Vec::new(),
+            ignored: Vec::new(),
+        }
+    }
+
+    /// The mapping used by the Whiley compiler (`wyc`), where
+    /// `whiley.verify` gives `--verify` (default `false`) and
+    /// `boogie.timeout` gives `--timeout <n>`.
+    pub fn wyc() -> Self {
+        Self::new()
+            .switch("whiley.verify", "--verify")
+            .with_default("whiley.verify", false)
+            .int("boogie.timeout", "--timeout")
+    }
+
+    /// Declare a boolean option which passes the given flag when
+    /// `true`.
+    pub fn switch<S: Into<String>, F: Into<String>>(self, key: S, flag: F) -> Self {
+        self.declare(key, Flag::Switch(flag.into()))
+    }
+
+    /// Declare a boolean option which passes the given flag when
+    /// `false`.
+    pub fn negated<S: Into<String>, F: Into<String>>(self, key: S, flag: F) -> Self {
+        self.declare(key, Flag::Negated(flag.into()))
+    }
+
+    /// Declare an integer option which passes the given flag followed
+    /// by its value.
+    pub fn int<S: Into<String>, F: Into<String>>(self, key: S, flag: F) -> Self {
+        self.declare(key, Flag::Int(flag.into()))
+    }
+
+    /// Declare a string option which passes the given flag followed by
+    /// its value.
+    pub fn string<S: Into<String>, F: Into<String>>(self, key: S, flag: F) -> Self {
+        self.declare(key, Flag::String(flag.into()))
+    }
+
+    /// Set the value of a declared option used when a test file does
+    /// not give one.
+    ///
+    /// # Panics
+    ///
+    /// If the option has not been declared, or the value has the wrong
+    /// type.
+    pub fn with_default<S: AsRef<str>, V: Into<Value<'static>>>(
+        mut self,
+        key: S,
+        value: V,
+    ) -> Self {
+        let key = key.as_ref();
+        let value = value.into();
+        let spec = self
+            .specs
+            .iter_mut()
+            .find(|s| s.key == key)
+            .unwrap_or_else(|| panic!("undeclared option `{}`", key));
+        assert!(spec.accepts(&value), "invalid default for `{}`", key);
+        spec.default = Some(value);
+        self
+    }
+
+    /// Accept (but pass nothing for) a given option or, when ending in
+    /// `.`, every option in a given namespace (e.g. `"js."`).
+    pub fn ignore<S: Into<String>>(mut self, key: S) -> Self {
+        self.ignored.push(key.into());
+        self
+    }
+
+    /// Check that every configuration option of a test file is known,
+    /// and has a value of the right type.
+    pub fn check(&self, wtf: &WhileyTestFile) -> OptionResult<()> {
+        let mut config: Vec<_> = wtf.config().collect();
+        config.sort_by_key(|(k, _)| *k);
+        for (key, value) in config {
+            let error = |kind| OptionError {
+                kind,
+                key: key.to_string(),
+            };
+            match self.specs.iter().find(|s| s.key == key) {
+                Some(s) if !s.accepts(value) => {
+                    return Err(error(OptionErrorKind::InvalidValue));
+                }
+                Some(_) => {}
+                None if self.is_ignored(key) => {}
+                None => return Err(error(OptionErrorKind::UnknownOption)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Determine the command-line arguments for the configuration
+    /// options of a test file.
+    pub fn args(&self, wtf: &WhileyTestFile) -> OptionResult<Vec<String>> {
+        self.check(wtf)?;
+        let mut args = Vec::new();
+        for spec in &self.specs {
+            let value = match wtf.get(&spec.key).or(spec.default.as_ref()) {
+                Some(v) => v,
+                None => continue,
+            };
+            match (&spec.flag, value) {
+                (Flag::Switch(f), Value::Bool(true)) | (Flag::Negated(f), Value::Bool(false)) => {
+                    args.push(f.clone());
+                }
+                (Flag::Int(f), Value::Int(i)) => {
+                    args.push(f.clone());
+                    args.push(i.to_string());
+                }
+                (Flag::String(f), Value::String(s)) => {
+                    args.push(f.clone());
+                    args.push(s.to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(args)
+    }
+
+    fn declare<S: Into<String>>(mut self, key: S, flag: Flag) -> Self {
+        let key = key.into();
+        self.specs.retain(|s| s.key != key);
+        self.specs.push(Spec {
+            key,
+            flag,
+            default: None,
+        });
+        self
+    }
+
+    fn is_ignored(&self, key: &str) -> bool {
+        self.ignored
+            .iter()
+            .any(|i| i == key || (i.ends_with('.') && key.starts_with(i.as_str())))
+    }
+}
+
+impl Spec {
+    /// Check whether a given value has the right type for this option.
+    fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (&self.flag, value),
+            (Flag::Switch(_) | Flag::Negated(_), Value::Bool(_))
+                | (Flag::Int(_), Value::Int(_))
+                | (Flag::String(_), Value::String(_))
+        )
+    }
+}
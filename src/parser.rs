@@ -1,19 +1,54 @@
 use crate::{
-    Action, Config, Coordinate, Error, Frame, Marker, Range, Result, Value, WhileyTestFile,
+    Action, Config, Coordinate, Error, ErrorKind, Frame, Marker, Range, Result, Severity, TestPath,
+    Value, WhileyTestFile,
 };
+use std::borrow::Cow;
+use std::ops;
+use std::result;
+
+/// The line which indicates that the line before it has no
+/// terminator (as used by `diff`).
+pub(crate) const NO_NEWLINE: &str = "\\ No newline at end of file";
+
+/// Identifies a problem within a given line, along with the offending
+/// text (which must be a slice of that line).
+pub(crate) type Failure<'a> = (ErrorKind, &'a str);
 
 pub struct Parser<'a> {
     // Identifies current line number.
     index: usize,
-    // Holds the set of lines.
-    lines: Vec<&'a str>,
+    // Holds the set of lines, along with their terminators.
+    lines: Vec<(&'a str, &'a str)>,
+    // Determines whether errors are recorded (rather than returned).
+    recovering: bool,
+    // Holds errors recorded whilst recovering.
+    errors: Vec<Error>,
 }
 
 impl<'a> Parser<'a> {
-    /// Construct a new parser from a given string slice.
+    /// Construct a new parser from a given string slice.  Lines are
+    /// split at `\n` only, so that any `\r` preceding it is retained
+    /// in the content of a file.
     pub fn new(input: &'a str) -> Self {
-        let lines = input.lines().collect();
-        Parser { index: 0, lines }
+        let lines = input
+            .split_inclusive('\n')
+            .map(|l| match l.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (l, ""),
+            })
+            .collect();
+        Self::from_lines(lines)
+    }
+
+    /// Construct a new parser from a given set of lines, each paired
+    /// with its terminator (i.e. `"\n"`, `"\r\n"` or `""`).
+    pub fn from_lines(lines: Vec<(&'a str, &'a str)>) -> Self {
+        Parser {
+            index: 0,
+            lines,
+            recovering: false,
+            errors: Vec::new(),
+        }
     }
 
     // ===============================================================
@@ -27,6 +62,21 @@ impl<'a> Parser<'a> {
         Ok(WhileyTestFile { config, frames })
     }
 
+    /// Parse configuration from this point whilst recovering from
+    /// errors.  A malformed configuration option or marker is simply
+    /// skipped, whilst a malformed action is skipped along with its
+    /// contents (i.e. up to the next line beginning with a prefix).
+    /// Lines which cannot follow the end of a frame (e.g. an action
+    /// after its markers) are skipped up to the next frame.
+    /// This returns whatever could be parsed, along with every error
+    /// encountered.
+    pub fn parse_recovering(&mut self) -> (WhileyTestFile<'a>, Vec<Error>) {
+        self.recovering = true;
+        // Errors are never returned in recovering mode.
+        let wtf = self.parse().unwrap();
+        (wtf, std::mem::take(&mut self.errors))
+    }
+
     /// Check whether end-of-file reached.
     pub fn eof(&self) -> bool {
         self.index >= self.lines.len()
@@ -35,16 +85,30 @@ impl<'a> Parser<'a> {
     /// Peek at next line in the file.
     pub fn peek(&self) -> &'a str {
         assert!(!self.eof());
-        self.lines[self.index]
+        self.lines[self.index].0
     }
 
     pub fn next(&mut self) -> &'a str {
         assert!(!self.eof());
-        let line = self.lines[self.index];
+        let (line, _) = self.lines[self.index];
         self.index += 1;
         line
     }
 
+    /// Get the next line as file content, which retains the `\r` of
+    /// a `\r\n` terminator.
+    fn next_content(&mut self) -> Cow<'a, str> {
+        assert!(!self.eof());
+        let (line, terminator) = self.lines[self.index];
+        self.index += 1;
+        let line = unescape(line);
+        if terminator.starts_with('\r') {
+            Cow::Owned(format!("{}\r", line))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
     // ===============================================================
     // Private Helpers
     // ===============================================================
@@ -57,8 +121,12 @@ impl<'a> Parser<'a> {
             let line = self.next().trim();
             // Skip empty lines.
             if !line.is_empty() {
-                let (k, v) = parse_kvp_line(line)?;
-                config.insert(k, v);
+                match parse_kvp_line(line) {
+                    Ok((k, v)) => {
+                        config.insert(k.into(), v);
+                    }
+                    Err(f) => self.recover(self.error(f))?,
+                }
             }
         }
         Ok(config)
@@ -68,8 +136,17 @@ impl<'a> Parser<'a> {
     fn parse_frames(&mut self) -> Result<Vec<Frame<'a>>> {
         let mut frames = Vec::new();
         // Parse as many frames as there are.
-        while !self.eof() && self.peek().starts_with("===") {
-            frames.push(self.parse_frame()?);
+        while !self.eof() {
+            if is_frame_prefix(self.peek()) {
+                frames.push(self.parse_frame()?);
+            } else {
+                // Something which cannot follow the end of a frame
+                let line = self.next().trim();
+                self.recover(self.error((ErrorKind::UnexpectedLine, line)))?;
+                while !self.eof() && !is_frame_prefix(self.peek()) {
+                    self.next();
+                }
+            }
         }
         Ok(frames)
     }
@@ -79,14 +156,23 @@ impl<'a> Parser<'a> {
         let mut actions = Vec::new();
         // Parse actiondelta's
         while !self.eof() && is_action_prefix(self.peek()) {
-            actions.push(self.parse_action()?);
+            match self.parse_action() {
+                Ok(action) => actions.push(action),
+                Err(err) => {
+                    self.recover(err)?;
+                    self.skip_to_prefix();
+                }
+            }
         }
         // Parse any markers
         let mut markers = Vec::new();
         if !self.eof() && is_marker_prefix(self.peek()) {
             self.next(); // skip prefix
             while !self.eof() && !is_prefix(self.peek()) {
-                markers.push(self.parse_marker()?);
+                match self.parse_marker() {
+                    Ok(marker) => markers.push(marker),
+                    Err(err) => self.recover(err)?,
+                }
             }
         }
         // Done
@@ -95,26 +181,44 @@ impl<'a> Parser<'a> {
 
     fn parse_action(&mut self) -> Result<Action<'a>> {
         let line = self.next().trim();
-        // Split action header by spaces.
-        let split: Vec<&str> = line.split(' ').collect();
-        // Parse filename and (optional) range.
-        let (filename, range) = match split.len() {
-            2 => (split[1], None),
-            3 => (split[1], Some(parse_range(split[2])?)),
+        // Split action header into tokens.
+        let mut tokens = tokenise(line).map_err(|f| self.error(f))?;
+        let kind = tokens[0].raw(line);
+        if kind != ">>>" && kind != "<<<" {
+            return Err(self.error((ErrorKind::InvalidAction, kind)));
+        }
+        // Parse filename and (optional) range, where only an insertion
+        // can have a range.
+        let range = match tokens.len() {
+            2 => None,
+            3 if kind == ">>>" => {
+                Some(parse_range(tokens[2].raw(line)).map_err(|f| self.error(f))?)
+            }
+            3 => return Err(self.error((ErrorKind::InvalidAction, tokens[2].raw(line)))),
             _ => {
-                return Err(Error::InvalidAction);
+                return Err(self.error((ErrorKind::InvalidAction, line)));
             }
         };
+        let filename = parse_filename(tokens.swap_remove(1), line).map_err(|f| self.error(f))?;
         // Parse action content
         let mut lines = Vec::new();
+        let mut newline = true;
         while !self.eof() && !is_prefix(self.peek()) {
-            lines.push(self.next());
+            if is_no_newline(self.peek()) {
+                let marker = self.next().trim_end_matches('\r');
+                if lines.is_empty() || !(self.eof() || is_prefix(self.peek())) {
+                    return Err(self.error((ErrorKind::UnexpectedNoNewline, marker)));
+                }
+                newline = false;
+            } else {
+                lines.push(self.next_content());
+            }
         }
         // Determine action kind
-        let act = if split[0] == ">>>" {
+        let act = if kind == ">>>" {
             match range {
-                Some(r) => Action::INSERT(filename, r, lines),
-                None => Action::CREATE(filename, lines),
+                Some(r) => Action::INSERT(filename, r, lines, newline),
+                None => Action::CREATE(filename, lines, newline),
             }
         } else {
             Action::REMOVE(filename)
@@ -127,36 +231,216 @@ impl<'a> Parser<'a> {
     /// given line and column in the file.
     fn parse_marker(&mut self) -> Result<Marker<'a>> {
         let line = self.next().trim();
-        // Split line into components
-        let split: Vec<&str> = line.split(' ').collect();
-        // Sanity check enough components
-        if split.len() == 3 {
-            let errno = parse_error_code(split[0])?;
-            let filename = split[1];
-            let location = parse_coordinate(split[2])?;
-            Ok(Marker {
-                errno,
-                filename,
-                location,
-            })
+        parse_marker_line(line).map_err(|f| self.error(f))
+    }
+
+    /// Handle an error arising during parsing.  When recovering, the
+    /// error is recorded and parsing continues.  Otherwise, the error
+    /// is simply returned.
+    fn recover(&mut self, err: Error) -> Result<()> {
+        if self.recovering {
+            self.errors.push(err);
+            Ok(())
         } else {
-            Err(Error::InvalidMarker)
+            Err(err)
+        }
+    }
+
+    /// Skip over lines until the next one beginning with a prefix
+    /// (e.g. `===`, `>>>`, etc) is reached.
+    fn skip_to_prefix(&mut self) {
+        while !self.eof() && !is_prefix(self.peek()) {
+            self.next();
+        }
+    }
+
+    /// Construct an error for a problem arising on the line most
+    /// recently consumed.  The offending text must be a slice of that
+    /// line, as this is used to determine the column span.
+    fn error(&self, (kind, token): Failure<'a>) -> Error {
+        let (text, _) = self.lines[self.index - 1];
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        let start = token.as_ptr() as usize - text.as_ptr() as usize;
+        Error {
+            kind,
+            line: self.index,
+            span: start..start + token.len(),
+            text: text.to_string(),
+            hint: hint(kind),
         }
     }
 }
 
+/// Provide a hint as to what was expected for a given kind of error.
+fn hint(kind: ErrorKind) -> Option<&'static str> {
+    let h = match kind {
+        ErrorKind::InvalidConfigOption => "expected `key = value`",
+        ErrorKind::InvalidConfigValue => "expected integer, string or boolean",
+        ErrorKind::InvalidStringValue => "expected quoted string without inner quotes",
+        ErrorKind::InvalidAction => "expected `>>> filename [range]` or `<<< filename`",
+        ErrorKind::InvalidRange => "expected `n` or `n:m`",
+        ErrorKind::InvalidMarker => "expected `code filename coordinate`",
+        ErrorKind::InvalidErrorCode => "expected code such as `E101`",
+        ErrorKind::InvalidCoordinate => "expected `line,n` or `line,n:m`",
+        ErrorKind::UnexpectedLine => "expected `===` to begin a new frame",
+        ErrorKind::InvalidFilename => {
+            "expected relative filename, or quoted filename such as `\"a b.whiley\"`"
+        }
+        _ => {
+            return None;
+        }
+    };
+    Some(h)
+}
+
+/// Parse a line of text containing a marker, such as:
+///
+/// ```text
+/// E101 main.whiley 1,2:3
+/// ```
+pub(crate) fn parse_marker_line(line: &str) -> result::Result<Marker<'_>, Failure<'_>> {
+    // Split line into tokens
+    let mut tokens = tokenise(line)?;
+    // Sanity check enough components
+    if tokens.len() == 3 {
+        let (severity, errno) = parse_error_code(tokens[0].raw(line))?;
+        let location = parse_coordinate(tokens[2].raw(line))?;
+        let filename = parse_filename(tokens.swap_remove(1), line)?;
+        Ok(Marker {
+            severity,
+            errno,
+            filename,
+            location,
+        })
+    } else {
+        Err((ErrorKind::InvalidMarker, line))
+    }
+}
+
+/// Parse a filename token, which must give a relative path within the
+/// project (see [`TestPath`]).
+fn parse_filename<'a>(
+    token: Token<'a>,
+    line: &'a str,
+) -> result::Result<TestPath<'a>, Failure<'a>> {
+    let raw = token.raw(line);
+    TestPath::new(token.text).map_err(|_| (ErrorKind::InvalidFilename, raw))
+}
+
+// ===============================================================
+// Tokeniser
+// ===============================================================
+
+/// A token within an action header or marker line.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Token<'a> {
+    /// Text of the token, with any quotes and escapes removed.
+    pub text: Cow<'a, str>,
+    /// Byte offsets of the token within the line.
+    pub span: ops::Range<usize>,
+}
+
+/// Split a line into tokens separated by runs of whitespace.  A token
+/// beginning with a quote extends to the next unescaped quote (and so
+/// may contain whitespace), where `\"` gives a quote.  A backslash
+/// can also be escaped (i.e. `\\`), though this is only a path
+/// separator within a filename.  For example, the following has
+/// three tokens:
+///
+/// ```text
+/// E101  "my \"main\".whiley"   1,2:3
+/// ```
+pub(crate) fn tokenise(line: &str) -> result::Result<Vec<Token<'_>>, Failure<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        // Find end of token
+        let mut end = line.len();
+        let mut text = None;
+        if c == '"' {
+            chars.next();
+            let mut unescaped = String::new();
+            let mut escaped = false;
+            let mut closed = false;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some((_, c @ ('"' | '\\'))) => {
+                            unescaped.push(c);
+                            escaped = true;
+                        }
+                        _ => return Err((ErrorKind::InvalidFilename, &line[start..])),
+                    },
+                    '"' => {
+                        end = i + 1;
+                        closed = true;
+                        break;
+                    }
+                    c => unescaped.push(c),
+                }
+            }
+            if !closed {
+                return Err((ErrorKind::InvalidFilename, &line[start..]));
+            }
+            text = Some(if escaped {
+                Cow::Owned(unescaped)
+            } else {
+                Cow::Borrowed(&line[start + 1..end - 1])
+            });
+        }
+        // Consume remainder of token (which must be empty if quoted)
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() {
+                if text.is_none() {
+                    end = i;
+                }
+                break;
+            } else if text.is_some() {
+                let n = line[i..]
+                    .find(char::is_whitespace)
+                    .map_or(line.len(), |n| i + n);
+                return Err((ErrorKind::InvalidFilename, &line[start..n]));
+            }
+            chars.next();
+        }
+        tokens.push(Token {
+            text: text.unwrap_or(Cow::Borrowed(&line[start..end])),
+            span: start..end,
+        });
+    }
+    Ok(tokens)
+}
+
+impl<'a> Token<'a> {
+    /// Get the raw text of this token (i.e. including any quotes and
+    /// escapes) within the line it was taken from.
+    pub fn raw(&self, line: &'a str) -> &'a str {
+        &line[self.span.clone()]
+    }
+}
+
+/// Determine whether a filename must be quoted when printed (i.e.
+/// because it is empty, contains whitespace or begins with a quote).
+pub(crate) fn needs_quotes(filename: &str) -> bool {
+    filename.is_empty() || filename.starts_with('"') || filename.contains(char::is_whitespace)
+}
+
 /// Parse a line of text containing a key-value assignment, such as:
 ///
 /// ```text
 /// wyc.compile = false
 /// ```
-fn parse_kvp_line(line: &str) -> Result<(&str, Value)> {
+pub(crate) fn parse_kvp_line(line: &str) -> result::Result<(&str, Value<'_>), Failure<'_>> {
     // Split line into components
     let bits: Vec<&str> = line.split('=').collect();
     // Sanity check only two components!
     if bits.len() != 2 {
         // Something is wrong!
-        Err(Error::InvalidConfigOption)
+        Err((ErrorKind::InvalidConfigOption, line))
     } else {
         let key = bits[0].trim();
         let value = parse_value(bits[1].trim())?;
@@ -165,7 +449,7 @@ fn parse_kvp_line(line: &str) -> Result<(&str, Value)> {
 }
 
 /// Parse a configuration object.
-fn parse_value(input: &str) -> Result<Value> {
+fn parse_value(input: &str) -> result::Result<Value<'_>, Failure<'_>> {
     // Extract first character.
     let c = input.chars().next();
     //
@@ -181,14 +465,14 @@ fn parse_value(input: &str) -> Result<Value> {
 
 /// Parse a string which should represent a (signed) integer value.
 /// If parsing fails for some reason, return appropriate error.
-fn parse_int_value(input: &str) -> Result<Value> {
+fn parse_int_value(input: &str) -> result::Result<Value<'_>, Failure<'_>> {
     match input.parse::<i64>() {
         Ok(i) => Ok(Value::Int(i)),
-        _ => Err(Error::InvalidIntValue),
+        _ => Err((ErrorKind::InvalidIntValue, input)),
     }
 }
 
-fn parse_string_value(input: &str) -> Result<Value> {
+fn parse_string_value(input: &str) -> result::Result<Value<'_>, Failure<'_>> {
     let n = input.len() - 1;
     // Check last element is quote
     if n > 0 && &input[n..] == "\"" {
@@ -197,26 +481,26 @@ fn parse_string_value(input: &str) -> Result<Value> {
         // Sanity check quotes don't appear within.
         if !content.contains('"') {
             // Success
-            return Ok(Value::String(content));
+            return Ok(Value::String(content.into()));
         }
     }
-    Err(Error::InvalidStringValue)
+    Err((ErrorKind::InvalidStringValue, input))
 }
 
-fn parse_bool_value(input: &str) -> Result<Value> {
+fn parse_bool_value(input: &str) -> result::Result<Value<'_>, Failure<'_>> {
     if input == "true" {
         Ok(Value::Bool(true))
     } else if input == "false" {
         Ok(Value::Bool(false))
     } else {
-        Err(Error::InvalidConfigValue)
+        Err((ErrorKind::InvalidConfigValue, input))
     }
 }
 
 /// Parse a "coodinate" which identifies a character range within a
 /// given line.  For example, `1,0:2` identifies the range `0:2`
 /// within line `1`.
-fn parse_coordinate(input: &str) -> Result<Coordinate> {
+fn parse_coordinate(input: &str) -> result::Result<Coordinate, Failure<'_>> {
     let split: Vec<&str> = input.split(',').collect();
     // Sanity check sufficient components
     if split.len() == 2 {
@@ -224,23 +508,23 @@ fn parse_coordinate(input: &str) -> Result<Coordinate> {
         let range = parse_range(split[1])?;
         Ok(Coordinate(line, range))
     } else {
-        Err(Error::InvalidCoordinate)
+        Err((ErrorKind::InvalidCoordinate, input))
     }
 }
 
 /// Parse an unsigned int which forms part of some coordinate.  In
 /// essence, this method just handles the mapping of error values.
-fn parse_coordinate_index(input: &str) -> Result<usize> {
+fn parse_coordinate_index(input: &str) -> result::Result<usize, Failure<'_>> {
     match input.parse::<usize>() {
         Ok(i) => Ok(i),
-        _ => Err(Error::InvalidCoordinate),
+        _ => Err((ErrorKind::InvalidCoordinate, input)),
     }
 }
 
 /// Parse a "range" which is either a single unsigned integer
 /// (e.g. `1`), or a pair of unsigned ints separated by a colon
 /// (e.g. `0:2`).
-fn parse_range(input: &str) -> Result<Range> {
+fn parse_range(input: &str) -> result::Result<Range, Failure<'_>> {
     let split: Vec<&str> = input.split(':').collect();
     // Match the kind of range we have.
     match split.len() {
@@ -253,50 +537,77 @@ fn parse_range(input: &str) -> Result<Range> {
             let j = parse_range_index(split[1])?;
             Ok(Range(i, j))
         }
-        _ => Err(Error::InvalidRange),
+        _ => Err((ErrorKind::InvalidRange, input)),
     }
 }
 
 /// Parse an unsigned int which forms part of some range.  In essence,
 /// this method just handles the mapping of error values.
-fn parse_range_index(input: &str) -> Result<usize> {
+fn parse_range_index(input: &str) -> result::Result<usize, Failure<'_>> {
     match input.parse::<usize>() {
         Ok(i) => Ok(i),
-        _ => Err(Error::InvalidRange),
+        _ => Err((ErrorKind::InvalidRange, input)),
     }
 }
 
 /// Parse an error code which is an identifier followed by an unsigned
-/// int (e.g. `E101`, `W23`, etc).
-fn parse_error_code(mut input: &str) -> Result<u16> {
-    input = &input[1..];
-    match input.parse::<u16>() {
-        Ok(i) => Ok(i),
-        _ => Err(Error::InvalidErrorCode),
+/// int (e.g. `E101`, `W23`, etc).  The identifier determines the
+/// severity of the marker.
+fn parse_error_code(input: &str) -> result::Result<(Severity, u16), Failure<'_>> {
+    let mut chars = input.chars();
+    match (chars.next(), chars.as_str().parse::<u16>()) {
+        (Some(c), Ok(i)) if c.is_ascii_alphabetic() => Ok((Severity::from_char(c), i)),
+        _ => Err((ErrorKind::InvalidErrorCode, input)),
     }
 }
 
 /// Determine whether the given string (which represents a line)
 /// begins with one of the key control markers (e.g. `===` which
 /// indicates the start of a frame, etc).
-fn is_prefix(line: &str) -> bool {
+pub(crate) fn is_prefix(line: &str) -> bool {
     is_frame_prefix(line) || is_action_prefix(line) || is_marker_prefix(line)
 }
 
+/// Determine whether a line of file content must be escaped (i.e.
+/// prefixed with `\`) when printed, since it would otherwise be read
+/// as a control line (or as [`NO_NEWLINE`]).  For example, `---` is
+/// printed as `\---`.  Lines which already begin with an escape that
+/// would otherwise be removed (e.g. `\---`) are themselves escaped, so
+/// that any content round-trips.
+pub(crate) fn needs_escape(line: &str) -> bool {
+    is_prefix(line) || is_no_newline(line) || line.strip_prefix('\\').is_some_and(needs_escape)
+}
+
+/// Remove the escape (if any) from a line of file content.  Only a
+/// backslash which precedes something needing escape is removed, so
+/// lines such as `\n` are left unchanged.
+pub(crate) fn unescape(line: &str) -> &str {
+    match line.strip_prefix('\\') {
+        Some(l) if needs_escape(l) => l,
+        _ => line,
+    }
+}
+
+/// Determine whether the given string (which represents a line)
+/// indicates that the line before it has no terminator.
+pub(crate) fn is_no_newline(line: &str) -> bool {
+    line.strip_suffix('\r').unwrap_or(line) == NO_NEWLINE
+}
+
 /// Determine whether the given string (which represents a line)
 /// identifies the start of a framer.
-fn is_frame_prefix(line: &str) -> bool {
+pub(crate) fn is_frame_prefix(line: &str) -> bool {
     line.starts_with("===")
 }
 
 /// Determine whether the given string (which represents a line)
 /// identifies the start of an action.
-fn is_action_prefix(line: &str) -> bool {
+pub(crate) fn is_action_prefix(line: &str) -> bool {
     line.starts_with(">>>") || line.starts_with("<<<")
 }
 
 /// Determine whether the given string (which represents a line)
 /// identifies the start of a marker block.
-fn is_marker_prefix(line: &str) -> bool {
+pub(crate) fn is_marker_prefix(line: &str) -> bool {
     line.starts_with("---")
 }
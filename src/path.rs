@@ -0,0 +1,233 @@
+use std::borrow::{Borrow, Cow};
+use std::error;
+use std::fmt;
+use std::ops;
+use std::path::{Path, PathBuf};
+use std::result;
+
+// ===============================================================
+// Path Error
+// ===============================================================
+
+/// Identifies the kind of problem encountered when constructing a
+/// [`TestPath`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathErrorKind {
+    /// A path which does not name a file (e.g. `""` or `"a/.."`).
+    Empty,
+    /// A path which is absolute (e.g. `/main.whiley` or `C:/main.whiley`).
+    Absolute,
+    /// A path which escapes the project root (e.g. `../main.whiley`).
+    Escaping,
+    /// A path containing a line terminator or nul character.
+    InvalidCharacter,
+}
+
+impl PathErrorKind {
+    /// Get a short description of this kind of error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            PathErrorKind::Empty => "empty path",
+            PathErrorKind::Absolute => "absolute path",
+            PathErrorKind::Escaping => "path escapes project",
+            PathErrorKind::InvalidCharacter => "invalid character in path",
+        }
+    }
+}
+
+/// Represents a problem encountered when constructing a [`TestPath`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathError {
+    /// Identifies what kind of problem this is.
+    pub kind: PathErrorKind,
+    /// The offending path.
+    pub path: String,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} \"{}\"", self.kind.message(), self.path)
+    }
+}
+
+impl error::Error for PathError {}
+
+pub type PathResult<T> = result::Result<T, PathError>;
+
+// ===============================================================
+// Test Path
+// ===============================================================
+
+/// The path of a file within the project described by a test file,
+/// relative to its root.  Paths are normalised on construction, such
+/// that either `/` or `\` separate components, empty and `.`
+/// components are dropped and `..` removes the preceding component.
+/// Thus, `./main.whiley`, `src/../main.whiley` and `main.whiley` are
+/// all the same path.  Absolute paths, and those which escape the
+/// project root, are rejected.
+///
+/// ```
+/// use whiley_test_file::TestPath;
+///
+/// let path = TestPath::new("src/./util/../main.whiley").unwrap();
+/// assert_eq!(path, "src/main.whiley");
+/// assert_eq!(path.parent(), Some("src"));
+/// assert_eq!(path.file_name(), "main.whiley");
+/// assert!(TestPath::new("../main.whiley").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TestPath<'a>(Cow<'a, str>);
+
+impl<'a> TestPath<'a> {
+    /// Construct a path, normalising it.  A path which is already in
+    /// normal form is not copied.
+    pub fn new<S: Into<Cow<'a, str>>>(path: S) -> PathResult<Self> {
+        let path = path.into();
+        let error = |kind| {
+            Err(PathError {
+                kind,
+                path: path.to_string(),
+            })
+        };
+        if path.contains(['\n', '\r', '\0']) {
+            return error(PathErrorKind::InvalidCharacter);
+        } else if is_absolute(&path) {
+            return error(PathErrorKind::Absolute);
+        }
+        let mut components = Vec::new();
+        for c in path.split(is_separator) {
+            match c {
+                "" | "." => {}
+                ".." => {
+                    if components.pop().is_none() {
+                        return error(PathErrorKind::Escaping);
+                    }
+                }
+                c => components.push(c),
+            }
+        }
+        if components.is_empty() {
+            return error(PathErrorKind::Empty);
+        }
+        let normal = components.join("/");
+        if normal == path {
+            Ok(TestPath(path))
+        } else {
+            Ok(TestPath(Cow::Owned(normal)))
+        }
+    }
+
+    /// Get this path as a string (using `/` as the separator).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Iterate the components of this path (e.g. `src` then
+    /// `main.whiley` for `src/main.whiley`).
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/')
+    }
+
+    /// Get the directory containing this file (if it is not at the
+    /// project root).
+    pub fn parent(&self) -> Option<&str> {
+        self.0.rsplit_once('/').map(|(d, _)| d)
+    }
+
+    /// Get the final component of this path.
+    pub fn file_name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap()
+    }
+
+    /// Determine the location of this file within a given directory.
+    /// Since a path cannot escape the project root, the result is
+    /// always within the given directory.
+    pub fn resolve<P: AsRef<Path>>(&self, root: P) -> PathBuf {
+        let mut path = root.as_ref().to_path_buf();
+        path.extend(self.components());
+        path
+    }
+
+    /// Convert this path into one which owns its string.
+    pub fn into_owned(self) -> TestPath<'static> {
+        TestPath(Cow::Owned(self.0.into_owned()))
+    }
+
+    /// Get the underlying string, which may be borrowed.
+    pub fn into_inner(self) -> Cow<'a, str> {
+        self.0
+    }
+}
+
+impl<'a> fmt::Display for TestPath<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> ops::Deref for TestPath<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> AsRef<str> for TestPath<'a> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> Borrow<str> for TestPath<'a> {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> PartialEq<str> for TestPath<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a, 'b> PartialEq<&'b str> for TestPath<'a> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<'a> TryFrom<&'a str> for TestPath<'a> {
+    type Error = PathError;
+
+    fn try_from(path: &'a str) -> PathResult<Self> {
+        TestPath::new(path)
+    }
+}
+
+impl<'a> TryFrom<String> for TestPath<'a> {
+    type Error = PathError;
+
+    fn try_from(path: String) -> PathResult<Self> {
+        TestPath::new(path)
+    }
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+/// Determine whether a path is absolute, either on Unix (e.g. `/a`) or
+/// Windows (e.g. `\a` or `C:\a`).
+fn is_absolute(path: &str) -> bool {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), _) if is_separator(c) => true,
+        (Some(c), Some(':')) => c.is_ascii_alphabetic(),
+        _ => false,
+    }
+}
@@ -0,0 +1,123 @@
+use crate::parser::{needs_escape, needs_quotes, NO_NEWLINE};
+use crate::{Action, Coordinate, Frame, Marker, Range, Value, WhileyTestFile};
+use std::fmt;
+
+/// The separator used to indicate the start of a frame.
+const FRAME_SEPARATOR: &str = "================";
+
+/// Print a test file in the format described by RFC#110.  Since the
+/// configuration is unordered, options are printed in order of their
+/// keys so that the output is deterministic.
+impl<'a> fmt::Display for WhileyTestFile<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys: Vec<&str> = self.config.keys().map(|k| k.as_ref()).collect();
+        keys.sort();
+        for k in keys {
+            writeln!(f, "{} = {}", k, self.config[k])?;
+        }
+        for frame in &self.frames {
+            write!(f, "{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Value<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Frame<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", FRAME_SEPARATOR)?;
+        for action in &self.actions {
+            write!(f, "{}", action)?;
+        }
+        writeln!(f, "---")?;
+        for marker in &self.markers {
+            writeln!(f, "{}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Action<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::CREATE(filename, _, _) => writeln!(f, ">>> {}", Filename(filename))?,
+            Action::INSERT(filename, range, _, _) => {
+                writeln!(f, ">>> {} {}", Filename(filename), range)?
+            }
+            Action::REMOVE(filename) => {
+                return writeln!(f, "<<< {}", Filename(filename));
+            }
+        }
+        for line in self.lines() {
+            if needs_escape(line) {
+                write!(f, "\\")?;
+            }
+            writeln!(f, "{}", line)?;
+        }
+        if !self.ends_with_newline() && !self.lines().is_empty() {
+            writeln!(f, "{}", NO_NEWLINE)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Marker<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {} {}",
+            self.severity.to_char(),
+            self.errno,
+            Filename(&self.filename),
+            self.location
+        )
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.0, self.1)
+    }
+}
+
+/// Print a range, using the short form (e.g. `1`) when it covers a
+/// single index and the long form (e.g. `0:2`) otherwise.
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == self.1 {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "{}:{}", self.0, self.1)
+        }
+    }
+}
+
+/// Print a filename, quoting it when necessary (e.g. when it contains
+/// whitespace).  Within quotes, `"` is escaped (whilst a normalised
+/// path never contains `\`).
+struct Filename<'a>(&'a str);
+
+impl<'a> fmt::Display for Filename<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !needs_quotes(self.0) {
+            return f.write_str(self.0);
+        }
+        write!(f, "\"")?;
+        for c in self.0.chars() {
+            if c == '"' {
+                write!(f, "\\")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        write!(f, "\"")
+    }
+}
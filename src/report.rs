@@ -0,0 +1,263 @@
+//! Reports of the results of running test files, in formats commonly
+//! understood by continuous integration systems.  Each frame of a test
+//! file is reported as a separate test case, with the name of the test
+//! file and index of the frame identifying it.  Failures include the
+//! difference between the expected and actual markers.  For example:
+//!
+//! ```
+//! use std::time::Duration;
+//! use whiley_test_file::report::Tap;
+//! use whiley_test_file::runner::{FrameResult, Outcome, TestResult};
+//!
+//! let frame = FrameResult { frame: 0, outcome: Outcome::Pass, duration: Duration::ZERO };
+//! let result = TestResult { name: "a.test".to_string(), frames: vec![frame] };
+//! assert_eq!(Tap(&[result]).to_string(), "TAP version 13\n1..1\nok 1 - a.test frame 0\n");
+//! ```
+use crate::runner::{FrameResult, Outcome, TestResult};
+use std::fmt;
+use std::time::Duration;
+
+// ===============================================================
+// JUnit
+// ===============================================================
+
+/// Prints test results as JUnit XML, with one `<testsuite>` per test
+/// file and one `<testcase>` per frame.  A frame which failed or timed
+/// out is reported as a `<failure>`, whilst one which could not be run
+/// is reported as an `<error>`.
+pub struct JUnit<'a>(pub &'a [TestResult]);
+
+impl<'a> fmt::Display for JUnit<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frames = self.0.iter().flat_map(|t| &t.frames);
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(f, "<testsuites {}>", Counts::of(frames))?;
+        for test in self.0 {
+            let name = escape(&test.name);
+            writeln!(
+                f,
+                r#"  <testsuite name="{}" {}>"#,
+                name,
+                Counts::of(&test.frames)
+            )?;
+            for frame in &test.frames {
+                write!(
+                    f,
+                    r#"    <testcase name="frame {}" classname="{}" time="{}""#,
+                    frame.frame,
+                    name,
+                    seconds(frame.duration)
+                )?;
+                let element = match &frame.outcome {
+                    Outcome::Pass => {
+                        writeln!(f, "/>")?;
+                        continue;
+                    }
+                    Outcome::Error(_) => "error",
+                    _ => "failure",
+                };
+                writeln!(f, ">")?;
+                writeln!(
+                    f,
+                    r#"      <{} type="{}" message="{}">{}</{}>"#,
+                    element,
+                    kind(&frame.outcome),
+                    escape(&message(test, frame)),
+                    escape(&details(&frame.outcome)),
+                    element
+                )?;
+                writeln!(f, "    </testcase>")?;
+            }
+            writeln!(f, "  </testsuite>")?;
+        }
+        writeln!(f, "</testsuites>")
+    }
+}
+
+/// The counts of frames given as attributes of a JUnit element.
+struct Counts {
+    tests: usize,
+    failures: usize,
+    errors: usize,
+    time: Duration,
+}
+
+impl Counts {
+    fn of<'a, I: IntoIterator<Item = &'a FrameResult>>(frames: I) -> Self {
+        let mut counts = Counts {
+            tests: 0,
+            failures: 0,
+            errors: 0,
+            time: Duration::ZERO,
+        };
+        for f in frames {
+            counts.tests += 1;
+            counts.time += f.duration;
+            match f.outcome {
+                Outcome::Pass => {}
+                Outcome::Fail(_) | Outcome::Timeout => counts.failures += 1,
+                Outcome::Error(_) => counts.errors += 1,
+            }
+        }
+        counts
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            r#"tests="{}" failures="{}" errors="{}" time="{}""#,
+            self.tests,
+            self.failures,
+            self.errors,
+            seconds(self.time)
+        )
+    }
+}
+
+// ===============================================================
+// TAP
+// ===============================================================
+
+/// Prints test results using the Test Anything Protocol (version 13),
+/// with one test point per frame.  Any frame which did not pass is
+/// followed by a YAML block giving its file, frame and outcome, along
+/// with the marker diff (for failures) or error message.
+pub struct Tap<'a>(pub &'a [TestResult]);
+
+impl<'a> fmt::Display for Tap<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total: usize = self.0.iter().map(|t| t.frames.len()).sum();
+        writeln!(f, "TAP version 13")?;
+        writeln!(f, "1..{}", total)?;
+        let mut n = 0;
+        for test in self.0 {
+            for frame in &test.frames {
+                n += 1;
+                let status = match frame.outcome {
+                    Outcome::Pass => "ok",
+                    _ => "not ok",
+                };
+                writeln!(f, "{} {} - {} frame {}", status, n, test.name, frame.frame)?;
+                if frame.outcome == Outcome::Pass {
+                    continue;
+                }
+                writeln!(f, "  ---")?;
+                writeln!(f, "  message: {}", quote(&message(test, frame)))?;
+                writeln!(f, "  file: {}", quote(&test.name))?;
+                writeln!(f, "  frame: {}", frame.frame)?;
+                writeln!(f, "  outcome: {}", kind(&frame.outcome))?;
+                writeln!(f, "  details: |")?;
+                for line in details(&frame.outcome).lines() {
+                    writeln!(f, "    {}", printable(line))?;
+                }
+                writeln!(f, "  ...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+/// A one-line summary of why a frame did not pass, identifying the
+/// file and frame concerned.
+fn message(test: &TestResult, frame: &FrameResult) -> String {
+    let what = match &frame.outcome {
+        Outcome::Pass => "passed".to_string(),
+        Outcome::Fail(_) => "markers differ".to_string(),
+        Outcome::Timeout => format!("timed out after {}s", seconds(frame.duration)),
+        Outcome::Error(e) => e.clone(),
+    };
+    format!("{} frame {}: {}", test.name, frame.frame, what)
+}
+
+/// Further details of why a frame did not pass (e.g. the marker diff
+/// for a failure).
+fn details(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Fail(cmp) => cmp.to_string(),
+        Outcome::Error(e) => format!("{}\n", e),
+        o => format!("{}\n", o),
+    }
+}
+
+/// The name of an outcome.
+fn kind(outcome: &Outcome) -> &'static str {
+    match outcome {
+        Outcome::Pass => "pass",
+        Outcome::Fail(_) => "fail",
+        Outcome::Timeout => "timeout",
+        Outcome::Error(_) => "error",
+    }
+}
+
+/// Format a duration in seconds, to millisecond precision.
+fn seconds(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64())
+}
+
+/// Escape text for inclusion in XML (either as content or within an
+/// attribute).  Characters which XML 1.0 does not permit at all (e.g.
+/// most control characters) are replaced with `U+FFFD`.
+fn escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            '\'' => s.push_str("&apos;"),
+            '\t' | '\n' | '\r' => s.push(c),
+            '\0'..='\x1f' | '\u{fffe}' | '\u{ffff}' => s.push(char::REPLACEMENT_CHARACTER),
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+/// Quote text as a YAML (double-quoted) string, escaping any
+/// character which is not printable in YAML.
+fn quote(text: &str) -> String {
+    let mut s = String::with_capacity(text.len() + 2);
+    s.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\t' => s.push_str("\\t"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            c if !is_printable(c) => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Replace any character which is not printable in YAML (other than a
+/// tab) with `U+FFFD`, for inclusion in a block scalar.
+fn printable(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\t' => c,
+            c if !is_printable(c) => char::REPLACEMENT_CHARACTER,
+            c => c,
+        })
+        .collect()
+}
+
+/// Determine whether a character is printable in YAML (i.e. is not a
+/// control character, line separator or byte order mark).
+fn is_printable(c: char) -> bool {
+    !c.is_control()
+        && !matches!(
+            c,
+            '\u{2028}' | '\u{2029}' | '\u{feff}' | '\u{fffe}' | '\u{ffff}'
+        )
+}
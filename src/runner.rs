@@ -0,0 +1,325 @@
+use crate::diagnostics::DiagnosticParser;
+use crate::options::Options;
+use crate::{
+    compare, Comparison, Marker, Materialiser, WhileyTestFile, COLUMN_TAB_WIDTH, COLUMN_UNIT,
+};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===============================================================
+// Results
+// ===============================================================
+
+/// Identifies the outcome of running the compiler on a single frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The markers produced matched those expected.
+    Pass,
+    /// The markers produced did not match those expected.
+    Fail(Comparison<'static>),
+    /// The compiler did not finish within the time allowed.
+    Timeout,
+    /// The frame could not be run (e.g. the compiler could not be
+    /// started, or its output could not be parsed).
+    Error(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail(_) => write!(f, "fail"),
+            Outcome::Timeout => write!(f, "timeout"),
+            Outcome::Error(e) => write!(f, "error ({})", e),
+        }
+    }
+}
+
+/// The result of running the compiler on a single frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameResult {
+    /// Index of the frame (starting from `0`).
+    pub frame: usize,
+    /// What happened.
+    pub outcome: Outcome,
+    /// How long the compiler took.
+    pub duration: Duration,
+}
+
+/// The result of running every frame of a test file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    /// Name of the test file.
+    pub name: String,
+    /// Results for each frame run.  If a frame cannot be run (e.g.
+    /// it cannot be applied), then it has an error outcome and no
+    /// further frames are run.
+    pub frames: Vec<FrameResult>,
+}
+
+impl TestResult {
+    /// Check whether every frame of this test passed.
+    pub fn passed(&self) -> bool {
+        self.frames.iter().all(|f| f.outcome == Outcome::Pass)
+    }
+}
+
+/// A summary of the frames run over some number of tests.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub tests: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub timeouts: usize,
+    pub errors: usize,
+}
+
+impl Summary {
+    /// Include the frames of a given test in this summary.
+    pub fn add(&mut self, result: &TestResult) {
+        self.tests += 1;
+        for f in &result.frames {
+            match f.outcome {
+                Outcome::Pass => self.passed += 1,
+                Outcome::Fail(_) => self.failed += 1,
+                Outcome::Timeout => self.timeouts += 1,
+                Outcome::Error(_) => self.errors += 1,
+            }
+        }
+    }
+
+    /// Check whether every frame summarised passed.
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.timeouts == 0 && self.errors == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} tests, {} frames: {} passed, {} failed, {} timed out, {} errors",
+            self.tests,
+            self.passed + self.failed + self.timeouts + self.errors,
+            self.passed,
+            self.failed,
+            self.timeouts,
+            self.errors
+        )
+    }
+}
+
+// ===============================================================
+// Runner
+// ===============================================================
+
+/// Responsible for running the frames of a test file through a
+/// compiler.  For each frame, the snapshot after that frame is
+/// materialised into a working directory and the compiler command is
+/// run there, with the snapshot's filenames appended as arguments
+/// (after any arguments given by the test's configuration options).
+/// Its output (i.e. standard output followed by standard error) is
+/// then parsed into markers, and compared against those expected.
+/// The compiler's exit status is ignored.
+///
+/// ```no_run
+/// use whiley_test_file::diagnostics::TextParser;
+/// use whiley_test_file::runner::Runner;
+/// use whiley_test_file::WhileyTestFile;
+///
+/// let runner = Runner::new(vec!["wyc".to_string()], TextParser::new());
+/// let wtf = WhileyTestFile::new("====\n>>> main.whiley\nmethod main():\n").unwrap();
+/// let result = runner.run("main", &wtf);
+/// assert!(result.passed());
+/// ```
+pub struct Runner {
+    command: Vec<String>,
+    parser: Box<dyn DiagnosticParser>,
+    options: Option<Options>,
+    timeout: Duration,
+    root: PathBuf,
+    /// Whether the root is the default one, which is removed when
+    /// empty.
+    temporary: bool,
+}
+
+impl Runner {
+    /// Construct a runner for a given compiler command (i.e. program
+    /// followed by any arguments), whose output is parsed using the
+    /// given parser.  By default, each frame has `10` seconds to
+    /// complete, and tests are materialised within a directory of the
+    /// system's temporary directory, which is removed after each test
+    /// (unless still in use).
+    pub fn new<P: DiagnosticParser + 'static>(command: Vec<String>, parser: P) -> Self {
+        assert!(!command.is_empty(), "missing compiler command");
+        Runner {
+            command,
+            parser: Box::new(parser),
+            options: None,
+            timeout: Duration::from_secs(10),
+            root: std::env::temp_dir().join(format!("wtf-run-{}", std::process::id())),
+            temporary: true,
+        }
+    }
+
+    /// Set the mapping used to turn the configuration options of each
+    /// test into arguments for the compiler.  By default, configuration
+    /// options are ignored.  Options describing the test file itself
+    /// (e.g. `column.unit`) are never passed to the compiler.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = Some(options.ignore(COLUMN_UNIT).ignore(COLUMN_TAB_WIDTH));
+        self
+    }
+
+    /// Set the time allowed for compiling each frame.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the directory within which tests are materialised.  Each
+    /// test is materialised within its own subdirectory, which is
+    /// removed afterwards.  The directory itself is never removed.
+    pub fn root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.root = root.into();
+        self.temporary = false;
+        self
+    }
+
+    /// Run every frame of a given test file.
+    pub fn run(&self, name: &str, wtf: &WhileyTestFile) -> TestResult {
+        let mut result = TestResult {
+            name: name.to_string(),
+            frames: Vec::new(),
+        };
+        let args = match self.options.as_ref().map(|o| o.args(wtf)) {
+            None => Vec::new(),
+            Some(Ok(args)) => args,
+            Some(Err(e)) => {
+                result.frames.push(FrameResult {
+                    frame: 0,
+                    outcome: Outcome::Error(e.to_string()),
+                    duration: Duration::ZERO,
+                });
+                return result;
+            }
+        };
+        let dir = self.root.join(sanitise(name));
+        let _ = fs::remove_dir_all(&dir);
+        let mut target = Materialiser::new(&dir);
+        for (i, snapshot) in wtf.snapshots().enumerate() {
+            let start = Instant::now();
+            let outcome = match snapshot {
+                Ok(s) => match target.write(&s) {
+                    Ok(_) => {
+                        let mut args = args.clone();
+                        args.extend(s.iter().map(|(f, _)| f.to_string()));
+                        self.compile(&dir, &args, &wtf.frame(i).markers)
+                    }
+                    Err(e) => Outcome::Error(e.to_string()),
+                },
+                Err(e) => Outcome::Error(e.to_string()),
+            };
+            let stop = matches!(outcome, Outcome::Error(_));
+            result.frames.push(FrameResult {
+                frame: i,
+                outcome,
+                duration: start.elapsed(),
+            });
+            if stop {
+                break;
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+        if self.temporary {
+            // Only succeeds once no other test is using it
+            let _ = fs::remove_dir(&self.root);
+        }
+        result
+    }
+
+    /// Run the compiler in a given directory with some arguments, and
+    /// compare the markers produced against those expected.
+    fn compile(&self, dir: &Path, args: &[String], expected: &[Marker]) -> Outcome {
+        let output = match self.execute(dir, args) {
+            Ok(Some(output)) => output,
+            Ok(None) => return Outcome::Timeout,
+            Err(e) => return Outcome::Error(format!("{}: {}", self.command[0], e)),
+        };
+        let actual = match self.parser.parse(&output) {
+            Ok(markers) => markers,
+            Err(e) => return Outcome::Error(e.to_string()),
+        };
+        let cmp = compare(expected, &actual);
+        if cmp.is_match() {
+            Outcome::Pass
+        } else {
+            Outcome::Fail(cmp.into_owned())
+        }
+    }
+
+    /// Execute the compiler command, returning its output or `None`
+    /// if it did not complete in time (in which case it is killed).
+    fn execute(&self, dir: &Path, args: &[String]) -> io::Result<Option<String>> {
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Read output concurrently, so the child never blocks on a
+        // full pipe.
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+        let start = Instant::now();
+        while child.try_wait()?.is_none() {
+            if start.elapsed() >= self.timeout {
+                child.kill()?;
+                child.wait()?;
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let mut output = stdout.join().unwrap_or_default();
+        output.push_str(&stderr.join().unwrap_or_default());
+        Ok(Some(output))
+    }
+}
+
+/// Read everything from a given stream on a separate thread.
+fn drain<R: Read + Send + 'static>(stream: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut s) = stream {
+            let _ = s.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+/// Turn the name of a test into something usable as a directory name
+/// within the root.  This is never empty and never begins with `.`,
+/// so it cannot identify the root itself (or its parent).
+fn sanitise(name: &str) -> String {
+    let mut dir: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if dir.is_empty() || dir.starts_with('.') {
+        dir.insert(0, '_');
+    }
+    dir
+}
@@ -0,0 +1,194 @@
+//! Support for serialising test files with [serde](https://serde.rs).
+//! The shape (e.g. in JSON) of each component is as follows:
+//!
+//! ```text
+//! WhileyTestFile = { "config": { key: Value, ... }, "frames": [Frame, ...] }
+//! Value          = string | integer | boolean
+//! Frame          = { "actions": [Action, ...], "markers": [Marker, ...] }
+//! Action         = { "kind": "create", "filename": string, "lines": [string, ...], "newline"?: boolean }
+//!                | { "kind": "insert", "filename": string, "range": Range, "lines": [string, ...], "newline"?: boolean }
+//!                | { "kind": "remove", "filename": string }
+//! Marker         = { "severity": string, "errno": integer, "filename": string, "location": Coordinate }
+//! Coordinate     = { "line": integer, "range": Range }
+//! Range          = [integer, integer]
+//! Comparison     = { "matched": [Marker, ...], "missing": [Marker, ...], "unexpected": [Marker, ...],
+//!                    "near_misses": [{ "kind": "wrong_location" | "wrong_code", "expected": Marker, "actual": Marker }, ...] }
+//! ```
+//!
+//! Configuration options are serialised in order of their keys, and
+//! the severity of a marker is given by its letter (e.g. `"E"`).  The
+//! `newline` field of an action is only present when its last line is
+//! unterminated (i.e. it defaults to `true`).
+//! Filenames are normalised when deserialised, and rejected if not
+//! valid (see [`TestPath`]).  Deserialised test files always own
+//! their strings.
+use crate::{Action, Config, Coordinate, Range, Severity, TestPath};
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+// ===============================================================
+// Config
+// ===============================================================
+
+/// Serialise configuration options in order of their keys, so that
+/// the output is deterministic.
+pub(crate) fn sorted<S: Serializer>(config: &Config, serializer: S) -> Result<S::Ok, S::Error> {
+    let sorted: BTreeMap<&str, _> = config.iter().map(|(k, v)| (k.as_ref(), v)).collect();
+    sorted.serialize(serializer)
+}
+
+// ===============================================================
+// Action
+// ===============================================================
+
+impl<'a> Serialize for Action<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Action::CREATE(filename, lines, newline) => {
+                let mut s = serializer.serialize_struct("Action", 4)?;
+                s.serialize_field("kind", "create")?;
+                s.serialize_field("filename", filename)?;
+                s.serialize_field("lines", lines)?;
+                serialize_newline(&mut s, *newline)?;
+                s.end()
+            }
+            Action::INSERT(filename, range, lines, newline) => {
+                let mut s = serializer.serialize_struct("Action", 5)?;
+                s.serialize_field("kind", "insert")?;
+                s.serialize_field("filename", filename)?;
+                s.serialize_field("range", range)?;
+                s.serialize_field("lines", lines)?;
+                serialize_newline(&mut s, *newline)?;
+                s.end()
+            }
+            Action::REMOVE(filename) => {
+                let mut s = serializer.serialize_struct("Action", 2)?;
+                s.serialize_field("kind", "remove")?;
+                s.serialize_field("filename", filename)?;
+                s.end()
+            }
+        }
+    }
+}
+
+/// The serialised form of an action.
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum ActionRepr<'a> {
+    Create {
+        filename: TestPath<'a>,
+        lines: Vec<Cow<'a, str>>,
+        #[serde(default = "terminated")]
+        newline: bool,
+    },
+    Insert {
+        filename: TestPath<'a>,
+        range: Range,
+        lines: Vec<Cow<'a, str>>,
+        #[serde(default = "terminated")]
+        newline: bool,
+    },
+    Remove {
+        filename: TestPath<'a>,
+    },
+}
+
+impl<'a> From<ActionRepr<'a>> for Action<'a> {
+    fn from(repr: ActionRepr<'a>) -> Self {
+        match repr {
+            ActionRepr::Create {
+                filename,
+                lines,
+                newline,
+            } => Action::CREATE(filename, lines, newline),
+            ActionRepr::Insert {
+                filename,
+                range,
+                lines,
+                newline,
+            } => Action::INSERT(filename, range, lines, newline),
+            ActionRepr::Remove { filename } => Action::REMOVE(filename),
+        }
+    }
+}
+
+/// Serialise whether the last line of an action is terminated, which
+/// is omitted in the common case that it is.
+fn serialize_newline<S: SerializeStruct>(s: &mut S, newline: bool) -> Result<(), S::Error> {
+    if newline {
+        s.skip_field("newline")
+    } else {
+        s.serialize_field("newline", &newline)
+    }
+}
+
+/// Actions are terminated by default.
+fn terminated() -> bool {
+    true
+}
+
+// ===============================================================
+// Path
+// ===============================================================
+
+impl<'a> Serialize for TestPath<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for TestPath<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        TestPath::new(path).map_err(de::Error::custom)
+    }
+}
+
+// ===============================================================
+// Severity
+// ===============================================================
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.to_char())
+    }
+}
+
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let c = char::deserialize(deserializer)?;
+        if c.is_ascii_alphabetic() {
+            Ok(Severity::from_char(c))
+        } else {
+            Err(de::Error::custom(format!("invalid severity '{}'", c)))
+        }
+    }
+}
+
+// ===============================================================
+// Coordinate
+// ===============================================================
+
+/// The serialised form of a coordinate.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CoordinateRepr {
+    line: usize,
+    range: Range,
+}
+
+impl From<CoordinateRepr> for Coordinate {
+    fn from(repr: CoordinateRepr) -> Self {
+        Coordinate(repr.line, repr.range)
+    }
+}
+
+impl From<Coordinate> for CoordinateRepr {
+    fn from(c: Coordinate) -> Self {
+        CoordinateRepr {
+            line: c.0,
+            range: c.1,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{Action, Frame, Range, TestPath};
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::result;
+use std::slice;
+
+// ===============================================================
+// Snapshot Error
+// ===============================================================
+
+/// Represents a problem encountered when applying an action to a
+/// snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// Attempt to insert into a file which does not exist.
+    InsertMissingFile(String),
+    /// Attempt to remove a file which does not exist.
+    RemoveMissingFile(String),
+    /// Attempt to insert using a range which falls outside the given
+    /// file (which has the given number of lines).
+    RangeOutOfBounds(String, Range, usize),
+    /// Attempt to insert lines whose last is unterminated before the
+    /// end of the given file.
+    UnterminatedInsert(String, Range),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::InsertMissingFile(n) => {
+                write!(f, "cannot insert into missing file {}", n)
+            }
+            SnapshotError::RemoveMissingFile(n) => {
+                write!(f, "cannot remove missing file {}", n)
+            }
+            SnapshotError::RangeOutOfBounds(n, r, len) => {
+                write!(f, "range {} out of bounds for {} ({} lines)", r, n, len)
+            }
+            SnapshotError::UnterminatedInsert(n, r) => {
+                write!(
+                    f,
+                    "cannot insert unterminated line at {} before end of {}",
+                    r, n
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for SnapshotError {}
+
+pub type SnapshotResult<T> = result::Result<T, SnapshotError>;
+
+// ===============================================================
+// Snapshot
+// ===============================================================
+
+/// Represents the state of a project at some point in a test file,
+/// which is determined by applying each frame in turn.  A `CREATE`
+/// action sets the contents of a file (replacing any existing
+/// contents), whilst a `REMOVE` action deletes a file.  An `INSERT`
+/// action with range `i:j` replaces lines `i` through `j` (inclusive,
+/// numbered from `1`) of an existing file.  Thus, `3` (or `3:3`)
+/// replaces only the third line.  The empty range `i:i-1` replaces no
+/// lines, and so inserts before line `i`.  For example, `1:0` inserts
+/// at the start of a file (including one which is empty), whilst
+/// `n+1:n` appends to a file with `n` lines.  A snapshot borrows its
+/// filenames and lines from the frames applied to it.  Each file also
+/// records whether it ends with a newline, which an `INSERT` can only
+/// change when it replaces the last line.
+///
+/// ```
+/// use whiley_test_file::{Snapshot, WhileyTestFile};
+///
+/// let wtf = WhileyTestFile::new("====\n>>> main.whiley\na\nb\n====\n>>> main.whiley 2\nc\nd").unwrap();
+/// let mut snapshot = Snapshot::new();
+/// snapshot.apply(wtf.frame(0)).unwrap();
+/// snapshot.apply(wtf.frame(1)).unwrap();
+/// assert_eq!(snapshot.get("main.whiley"), Some(&["a", "c", "d"][..]));
+/// assert_eq!(snapshot.contents("main.whiley").unwrap(), "a\nc\nd\n");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot<'a> {
+    files: BTreeMap<&'a str, File<'a>>,
+}
+
+/// The state of a single file within a snapshot.
+#[derive(Clone, Debug, PartialEq)]
+struct File<'a> {
+    lines: Vec<&'a str>,
+    // Determines whether the last line is terminated.
+    newline: bool,
+}
+
+impl<'a> Snapshot<'a> {
+    /// Construct an empty snapshot (i.e. with no files).
+    pub fn new() -> Self {
+        Snapshot {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Get the current lines of a given file (if it exists).  The
+    /// filename is normalised first, so `./main.whiley` and
+    /// `main.whiley` identify the same file.
+    pub fn get(&self, filename: &str) -> Option<&[&'a str]> {
+        self.file(filename).map(|f| &f.lines[..])
+    }
+
+    /// Check whether a given file ends with a newline (if it exists).
+    /// An empty file is always considered to end with a newline.
+    pub fn ends_with_newline(&self, filename: &str) -> Option<bool> {
+        self.file(filename).map(|f| f.newline)
+    }
+
+    /// Get the contents of a given file (if it exists) exactly as they
+    /// should be written to disk.  Every line is followed by `\n`,
+    /// except the last when the file does not end with a newline.
+    /// Lines which end with `\r` give `\r\n` terminators.
+    pub fn contents(&self, filename: &str) -> Option<String> {
+        let file = self.file(filename)?;
+        let mut contents = file.lines.join("\n");
+        if file.newline && !file.lines.is_empty() {
+            contents.push('\n');
+        }
+        Some(contents)
+    }
+
+    /// Check whether a given file exists in this snapshot (see
+    /// [`get()`](Self::get)).
+    pub fn contains(&self, filename: &str) -> bool {
+        self.get(filename).is_some()
+    }
+
+    /// Get number of files in this snapshot.
+    pub fn size(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Iterate the files in this snapshot (in order of their names),
+    /// along with their current lines.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &[&'a str])> {
+        self.files.iter().map(|(k, v)| (*k, &v.lines[..]))
+    }
+
+    /// Apply every action in a given frame, in order of appearance.
+    /// If any action cannot be applied, then an error is returned and
+    /// this snapshot is left unchanged.
+    pub fn apply(&mut self, frame: &'a Frame) -> SnapshotResult<()> {
+        let mut next = self.clone();
+        for action in &frame.actions {
+            next.apply_action(action)?;
+        }
+        *self = next;
+        Ok(())
+    }
+
+    /// Apply a single action to this snapshot.  If the action cannot
+    /// be applied, then an error is returned and this snapshot is left
+    /// unchanged.
+    pub fn apply_action(&mut self, action: &'a Action) -> SnapshotResult<()> {
+        match action {
+            Action::CREATE(filename, lines, newline) => {
+                let file = File {
+                    lines: lines.iter().map(|l| l.as_ref()).collect(),
+                    newline: *newline || lines.is_empty(),
+                };
+                self.files.insert(filename.as_str(), file);
+            }
+            Action::REMOVE(filename) => {
+                if self.files.remove(filename.as_ref()).is_none() {
+                    return Err(SnapshotError::RemoveMissingFile(filename.to_string()));
+                }
+            }
+            Action::INSERT(filename, range, lines, newline) => {
+                let file = match self.files.get_mut(filename.as_ref()) {
+                    Some(file) => file,
+                    None => {
+                        return Err(SnapshotError::InsertMissingFile(filename.to_string()));
+                    }
+                };
+                let Range(i, j) = *range;
+                let len = file.lines.len();
+                if i == 0 || i > j.saturating_add(1) || j > len {
+                    return Err(SnapshotError::RangeOutOfBounds(
+                        filename.to_string(),
+                        *range,
+                        len,
+                    ));
+                }
+                // Only the last line of a file can be unterminated.
+                if j == len {
+                    file.newline = *newline || lines.is_empty();
+                } else if !*newline && !lines.is_empty() {
+                    return Err(SnapshotError::UnterminatedInsert(
+                        filename.to_string(),
+                        *range,
+                    ));
+                }
+                file.lines
+                    .splice(i - 1..j, lines.iter().map(|l| l.as_ref()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the state of a given file (if it exists), normalising its
+    /// name first.
+    fn file(&self, filename: &str) -> Option<&File<'a>> {
+        let path = TestPath::new(filename).ok()?;
+        self.files.get(path.as_str())
+    }
+}
+
+// ===============================================================
+// Snapshots
+// ===============================================================
+
+/// An iterator over the snapshots of a test file, yielding the state
+/// of the project after each frame in turn.  If a frame cannot be
+/// applied, then the corresponding error is yielded and iteration
+/// stops.
+pub struct Snapshots<'a> {
+    frames: slice::Iter<'a, Frame<'a>>,
+    current: Option<Snapshot<'a>>,
+}
+
+impl<'a> Snapshots<'a> {
+    pub(crate) fn new(frames: &'a [Frame<'a>]) -> Self {
+        Snapshots {
+            frames: frames.iter(),
+            current: Some(Snapshot::new()),
+        }
+    }
+}
+
+impl<'a> Iterator for Snapshots<'a> {
+    type Item = SnapshotResult<Snapshot<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.next()?;
+        let snapshot = self.current.as_mut()?;
+        match snapshot.apply(frame) {
+            Ok(()) => Some(Ok(snapshot.clone())),
+            Err(err) => {
+                self.current = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
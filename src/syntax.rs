@@ -0,0 +1,404 @@
+//! A lossless _concrete syntax tree_ for test files.  Unlike
+//! [`WhileyTestFile`], this retains every byte of the original input
+//! (e.g. the length of frame separators, blank lines, trailing
+//! whitespace, line terminators and the order of configuration
+//! options).  Printing an unmodified tree reproduces its input
+//! exactly, whilst edits made through the tree only affect the lines
+//! concerned.  This makes it suitable for automated rewriting of test
+//! files.
+//!
+//! ```
+//! use whiley_test_file::syntax::SyntaxTree;
+//! use whiley_test_file::Value;
+//!
+//! let input = "b = 1\na = false\n====\n>>> main.whiley\nmethod main()\n";
+//! let mut tree = SyntaxTree::new(input);
+//! assert_eq!(tree.to_string(), input);
+//! tree.set_config("a", &Value::Bool(true));
+//! assert_eq!(tree.to_string(), "b = 1\na = true\n====\n>>> main.whiley\nmethod main()\n");
+//! ```
+use crate::parser::{
+    is_action_prefix, is_frame_prefix, is_marker_prefix, is_prefix, parse_kvp_line,
+    parse_marker_line, Parser,
+};
+use crate::{Marker, Result, Value, WhileyTestFile};
+use std::borrow::Cow;
+use std::fmt;
+use std::iter;
+
+// ===============================================================
+// Line
+// ===============================================================
+
+/// Represents a single line of the original input, split into its
+/// text and its line terminator (i.e. `"\n"`, `"\r\n"` or `""` for a
+/// final line without one).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line<'a> {
+    pub text: Cow<'a, str>,
+    pub terminator: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// Construct a new line from some text and a terminator.
+    pub fn new<T: Into<Cow<'a, str>>>(text: T, terminator: &'a str) -> Self {
+        Line {
+            text: text.into(),
+            terminator,
+        }
+    }
+
+    /// Split a raw line (which may include its terminator) into its
+    /// components.
+    fn split(raw: &'a str) -> Self {
+        let n = if raw.ends_with("\r\n") {
+            2
+        } else if raw.ends_with('\n') {
+            1
+        } else {
+            0
+        };
+        let (text, terminator) = raw.split_at(raw.len() - n);
+        Line::new(text, terminator)
+    }
+}
+
+impl<'a> fmt::Display for Line<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.text, self.terminator)
+    }
+}
+
+// ===============================================================
+// Marker Change
+// ===============================================================
+
+/// Identifies a marker line changed when replacing the markers of a
+/// frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkerChange {
+    /// A line was added to the marker block of the given frame.
+    Add(usize, String),
+    /// A line was removed from the marker block of the given frame.
+    Remove(usize, String),
+}
+
+impl fmt::Display for MarkerChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkerChange::Add(n, l) => write!(f, "frame {}: +{}", n, l),
+            MarkerChange::Remove(n, l) => write!(f, "frame {}: -{}", n, l),
+        }
+    }
+}
+
+// ===============================================================
+// Syntax Tree
+// ===============================================================
+
+/// The root of a concrete syntax tree.  The `preamble` holds every
+/// line before the first frame (i.e. configuration options and blank
+/// lines), whilst `trailing` holds any lines after the last frame
+/// which do not form part of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxTree<'a> {
+    pub preamble: Vec<Line<'a>>,
+    pub frames: Vec<FrameSyntax<'a>>,
+    pub trailing: Vec<Line<'a>>,
+}
+
+/// A frame within the syntax tree, consisting of its separator line
+/// (e.g. `====`), its actions and an optional block of markers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameSyntax<'a> {
+    pub separator: Line<'a>,
+    pub actions: Vec<ActionSyntax<'a>>,
+    pub markers: Option<BlockSyntax<'a>>,
+}
+
+/// An action within the syntax tree, consisting of its header line
+/// (e.g. `>>> main.whiley 1:2`) and the lines of its body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionSyntax<'a> {
+    pub header: Line<'a>,
+    pub body: Vec<Line<'a>>,
+}
+
+/// A block of markers within the syntax tree, consisting of its
+/// header line (e.g. `---`) and the marker lines themselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockSyntax<'a> {
+    pub header: Line<'a>,
+    pub body: Vec<Line<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Construct a syntax tree from a given input string.  This never
+    /// fails since, unlike parsing, it only needs to classify lines
+    /// according to their prefix.  The lines are classified exactly
+    /// as the parser would.
+    pub fn new(input: &'a str) -> Self {
+        let mut lines = input.split_inclusive('\n').map(Line::split).peekable();
+        // Everything upto the first frame
+        let mut preamble = Vec::new();
+        while let Some(line) = lines.next_if(|l| !is_frame_prefix(&l.text)) {
+            preamble.push(line);
+        }
+        // Frames
+        let mut frames = Vec::new();
+        while let Some(separator) = lines.next_if(|l| is_frame_prefix(&l.text)) {
+            let mut actions = Vec::new();
+            while let Some(header) = lines.next_if(|l| is_action_prefix(&l.text)) {
+                let body = parse_body(&mut lines);
+                actions.push(ActionSyntax { header, body });
+            }
+            let markers = lines
+                .next_if(|l| is_marker_prefix(&l.text))
+                .map(|header| BlockSyntax {
+                    header,
+                    body: parse_body(&mut lines),
+                });
+            frames.push(FrameSyntax {
+                separator,
+                actions,
+                markers,
+            });
+        }
+        // Anything left over
+        let trailing = lines.collect();
+        SyntaxTree {
+            preamble,
+            frames,
+            trailing,
+        }
+    }
+
+    /// Parse this syntax tree into a test file.  Line numbers in any
+    /// error reported correspond to lines of this tree.
+    pub fn parse(&self) -> Result<WhileyTestFile<'_>> {
+        let lines = self
+            .lines()
+            .map(|l| (l.text.as_ref(), l.terminator))
+            .collect();
+        Parser::from_lines(lines).parse()
+    }
+
+    /// Iterate every line of this tree in order.
+    pub fn lines(&self) -> impl Iterator<Item = &Line<'a>> {
+        self.preamble
+            .iter()
+            .chain(self.frames.iter().flat_map(|f| f.lines()))
+            .chain(self.trailing.iter())
+    }
+
+    /// Iterate every line of this tree in order, allowing them to be
+    /// modified.
+    pub fn lines_mut(&mut self) -> impl Iterator<Item = &mut Line<'a>> {
+        self.preamble
+            .iter_mut()
+            .chain(self.frames.iter_mut().flat_map(|f| f.lines_mut()))
+            .chain(self.trailing.iter_mut())
+    }
+
+    /// Determine the line terminator used by this tree.  This is the
+    /// terminator of the first terminated line or, if there is none,
+    /// `"\n"`.
+    pub fn newline(&self) -> &'a str {
+        self.lines()
+            .map(|l| l.terminator)
+            .find(|t| !t.is_empty())
+            .unwrap_or("\n")
+    }
+
+    /// Set the value of a configuration option.  If the option
+    /// already exists, then its line is rewritten in place.
+    /// Otherwise, a new line is added after the last configuration
+    /// option.
+    pub fn set_config(&mut self, key: &str, value: &Value) {
+        let text = format!("{} = {}", key, value);
+        match self.find_config(key) {
+            Some(i) => {
+                self.preamble[i].text = Cow::Owned(text);
+            }
+            None => {
+                let i = self
+                    .preamble
+                    .iter()
+                    .rposition(|l| !l.text.trim().is_empty())
+                    .map_or(0, |i| i + 1);
+                self.edit(|t, nl| t.preamble.insert(i, Line::new(text, nl)));
+            }
+        }
+    }
+
+    /// Remove a configuration option, returning `true` if it existed.
+    pub fn remove_config(&mut self, key: &str) -> bool {
+        match self.find_config(key) {
+            Some(i) => {
+                self.edit(|t, _| {
+                    t.preamble.remove(i);
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the markers of a given frame, returning the marker
+    /// lines which were added or removed.  Existing marker lines which
+    /// are equivalent to a given marker are retained as is (including
+    /// their formatting and order), so only lines for markers which
+    /// actually changed are affected.  Lines for new markers are added
+    /// at the end of the block.  If the frame has no marker block, then
+    /// one is added (unless there are no markers).
+    pub fn set_markers(&mut self, frame: usize, markers: &[Marker]) -> Vec<MarkerChange> {
+        let n = frame;
+        let mut changes = Vec::new();
+        if markers.is_empty() && self.frames[n].markers.is_none() {
+            return changes;
+        }
+        self.edit(|t, nl| {
+            let frame = &mut t.frames[n];
+            let block = frame.markers.get_or_insert_with(|| BlockSyntax {
+                header: Line::new("---", nl),
+                body: Vec::new(),
+            });
+            let old: Vec<Line<'a>> = block.body.drain(..).collect();
+            let mut kept = vec![false; old.len()];
+            let mut added = Vec::new();
+            for m in markers {
+                // Look for an existing equivalent line
+                let existing = old
+                    .iter()
+                    .zip(&kept)
+                    .position(|(l, k)| !k && parse_marker_line(l.text.trim()).as_ref() == Ok(m));
+                match existing {
+                    Some(i) => kept[i] = true,
+                    None => added.push(m),
+                }
+            }
+            // Retained lines keep their order, with others added after
+            for (l, k) in old.into_iter().zip(kept) {
+                if k {
+                    block.body.push(l);
+                } else {
+                    changes.push(MarkerChange::Remove(n, l.text.into_owned()));
+                }
+            }
+            for m in added {
+                changes.push(MarkerChange::Add(n, m.to_string()));
+                block.body.push(Line::new(m.to_string(), nl));
+            }
+        });
+        changes
+    }
+
+    /// Replace the markers of every frame with those actually
+    /// produced (e.g. by a compiler), returning the marker lines which
+    /// were added or removed.  The nth set of markers given replaces
+    /// those of the nth frame, and any frames beyond those given are
+    /// left unchanged.  Lines outside of marker blocks are never
+    /// affected.
+    ///
+    /// ```
+    /// use whiley_test_file::syntax::{MarkerChange, SyntaxTree};
+    /// use whiley_test_file::WhileyTestFile;
+    ///
+    /// let mut tree = SyntaxTree::new("====\n>>> main.whiley\nx\n---\nE101 main.whiley 1,0\n");
+    /// let actual = WhileyTestFile::new("====\n---\nE102 main.whiley 1,0\n").unwrap();
+    /// let changes = tree.bless(&[actual.frame(0).markers.clone()]);
+    /// assert_eq!(tree.to_string(), "====\n>>> main.whiley\nx\n---\nE102 main.whiley 1,0\n");
+    /// assert_eq!(changes.len(), 2);
+    /// ```
+    pub fn bless(&mut self, actual: &[Vec<Marker>]) -> Vec<MarkerChange> {
+        let mut changes = Vec::new();
+        for (n, markers) in actual.iter().enumerate().take(self.frames.len()) {
+            changes.extend(self.set_markers(n, markers));
+        }
+        changes
+    }
+
+    /// Find the index within the preamble of the configuration option
+    /// with the given key (if it exists).
+    fn find_config(&self, key: &str) -> Option<usize> {
+        self.preamble
+            .iter()
+            .position(|l| matches!(parse_kvp_line(l.text.trim()), Ok((k, _)) if k == key))
+    }
+
+    /// Apply an edit to this tree which may add, remove or move
+    /// lines.  Afterwards, line terminators are repaired so that only
+    /// the final line can be unterminated, and it is unterminated only
+    /// if the final line was originally.
+    fn edit<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self, &'a str),
+    {
+        let newline = self.newline();
+        let unterminated = matches!(self.lines().last(), Some(l) if l.terminator.is_empty());
+        f(self, newline);
+        let mut lines = self.lines_mut().peekable();
+        while let Some(line) = lines.next() {
+            if lines.peek().is_none() && unterminated {
+                line.terminator = "";
+            } else if line.terminator.is_empty() {
+                line.terminator = newline;
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for SyntaxTree<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines() {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FrameSyntax<'a> {
+    /// Iterate every line of this frame in order.
+    pub fn lines(&self) -> impl Iterator<Item = &Line<'a>> {
+        iter::once(&self.separator)
+            .chain(
+                self.actions
+                    .iter()
+                    .flat_map(|a| iter::once(&a.header).chain(a.body.iter())),
+            )
+            .chain(
+                self.markers
+                    .iter()
+                    .flat_map(|b| iter::once(&b.header).chain(b.body.iter())),
+            )
+    }
+
+    /// Iterate every line of this frame in order, allowing them to be
+    /// modified.
+    pub fn lines_mut(&mut self) -> impl Iterator<Item = &mut Line<'a>> {
+        iter::once(&mut self.separator)
+            .chain(
+                self.actions
+                    .iter_mut()
+                    .flat_map(|a| iter::once(&mut a.header).chain(a.body.iter_mut())),
+            )
+            .chain(
+                self.markers
+                    .iter_mut()
+                    .flat_map(|b| iter::once(&mut b.header).chain(b.body.iter_mut())),
+            )
+    }
+}
+
+/// Consume lines upto (but not including) the next one beginning
+/// with a prefix.
+fn parse_body<'a, I>(lines: &mut iter::Peekable<I>) -> Vec<Line<'a>>
+where
+    I: Iterator<Item = Line<'a>>,
+{
+    let mut body = Vec::new();
+    while let Some(line) = lines.next_if(|l| !is_prefix(&l.text)) {
+        body.push(line);
+    }
+    body
+}
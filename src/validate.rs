@@ -0,0 +1,164 @@
+use crate::options::OptionError;
+use crate::{Action, Columns, Frame, Marker, Range, Snapshot, SnapshotError};
+use std::fmt;
+
+// ===============================================================
+// Violation
+// ===============================================================
+
+/// Identifies the item within a frame to which a violation relates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Item {
+    /// The configuration of the test file (in which case the frame is
+    /// always `0`).
+    Config,
+    /// The nth action of the frame.
+    Action(usize),
+    /// The nth marker of the frame.
+    Marker(usize),
+}
+
+/// Identifies the kind of problem found when validating a test file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// A configuration option describing the test file itself (e.g.
+    /// `column.unit`) has an invalid value.
+    InvalidOption(OptionError),
+    /// An action could not be applied to the snapshot at that point.
+    Snapshot(SnapshotError),
+    /// A range whose start is after its end.
+    InvalidRange(Range),
+    /// An `INSERT` whose range overlaps that of an earlier `INSERT`
+    /// (with the given index) on the same file in the same frame.
+    OverlappingInsert(usize),
+    /// A marker naming a file which does not exist in the snapshot.
+    MissingFile(String),
+    /// A marker whose line falls outside its file (which has the
+    /// given number of lines).
+    LineOutOfBounds(usize, usize),
+    /// A marker whose range falls outside its line (which has the
+    /// given width in columns).
+    ColumnOutOfBounds(Range, usize),
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViolationKind::InvalidOption(e) => write!(f, "{}", e),
+            ViolationKind::Snapshot(e) => write!(f, "{}", e),
+            ViolationKind::InvalidRange(r) => write!(f, "invalid range {}", r),
+            ViolationKind::OverlappingInsert(i) => {
+                write!(f, "overlaps insert action {}", i)
+            }
+            ViolationKind::MissingFile(n) => write!(f, "missing file {}", n),
+            ViolationKind::LineOutOfBounds(l, len) => {
+                write!(f, "line {} out of bounds ({} lines)", l, len)
+            }
+            ViolationKind::ColumnOutOfBounds(r, len) => {
+                write!(f, "range {} out of bounds ({} columns)", r, len)
+            }
+        }
+    }
+}
+
+/// Represents a problem found when validating a test file, which
+/// identifies the frame and item within that frame concerned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// Index of the frame concerned (starting from `0`).
+    pub frame: usize,
+    /// The action or marker concerned.
+    pub item: Item,
+    /// Identifies what kind of problem this is.
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.item {
+            Item::Config => write!(f, "configuration: ")?,
+            Item::Action(i) => write!(f, "frame {}, action {}: ", self.frame, i)?,
+            Item::Marker(i) => write!(f, "frame {}, marker {}: ", self.frame, i)?,
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+// ===============================================================
+// Validation
+// ===============================================================
+
+/// Validate a sequence of frames, returning every violation found.
+/// Actions which cannot be applied are reported and then skipped, so
+/// that later frames are still validated.
+pub(crate) fn validate(frames: &[Frame], columns: &Columns) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut snapshot = Snapshot::new();
+    for (n, frame) in frames.iter().enumerate() {
+        let mut report = |item, kind| {
+            violations.push(Violation {
+                frame: n,
+                item,
+                kind,
+            })
+        };
+        for (i, action) in frame.actions.iter().enumerate() {
+            if let Some(kind) = check_action(&frame.actions[..i], action) {
+                report(Item::Action(i), kind);
+            } else if let Err(e) = snapshot.apply_action(action) {
+                report(Item::Action(i), ViolationKind::Snapshot(e));
+            }
+        }
+        for (i, marker) in frame.markers.iter().enumerate() {
+            if let Some(kind) = check_marker(&snapshot, marker, columns) {
+                report(Item::Marker(i), kind);
+            }
+        }
+    }
+    violations
+}
+
+/// Check an action against those preceding it in the same frame.
+fn check_action(preceding: &[Action], action: &Action) -> Option<ViolationKind> {
+    if let Action::INSERT(filename, range, _, _) = action {
+        // An empty range (i.e. `i:i-1`) inserts without replacing
+        if range.0 > range.1.saturating_add(1) {
+            return Some(ViolationKind::InvalidRange(*range));
+        }
+        for (i, a) in preceding.iter().enumerate() {
+            match a {
+                Action::INSERT(f, r, _, _) if f == filename && overlaps(r, range) => {
+                    return Some(ViolationKind::OverlappingInsert(i));
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Check a marker against the snapshot to which it applies.
+fn check_marker(snapshot: &Snapshot, marker: &Marker, columns: &Columns) -> Option<ViolationKind> {
+    let lines = match snapshot.get(&marker.filename) {
+        Some(lines) => lines,
+        None => return Some(ViolationKind::MissingFile(marker.filename.to_string())),
+    };
+    let (line, range) = (marker.location.0, marker.location.1);
+    if line == 0 || line > lines.len() {
+        return Some(ViolationKind::LineOutOfBounds(line, lines.len()));
+    }
+    if range.0 > range.1 {
+        return Some(ViolationKind::InvalidRange(range));
+    }
+    let text = lines[line - 1];
+    let len = columns.width(text.strip_suffix('\r').unwrap_or(text));
+    if range.1 > len {
+        return Some(ViolationKind::ColumnOutOfBounds(range, len));
+    }
+    None
+}
+
+/// Determine whether two (inclusive) ranges overlap.
+fn overlaps(a: &Range, b: &Range) -> bool {
+    a.0 <= b.1 && b.0 <= a.1
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use whiley_test_file::WhileyTestFile;
+
+// ===============================================================
+// Command-Line Tests
+// ===============================================================
+
+#[test]
+fn cli_usage_01() {
+    let out = wtf(&[]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn cli_check_01() {
+    let dir = temp_dir("cli_check_01");
+    write(
+        &dir,
+        "a.test",
+        "====\n>>> main.whiley\nmethod main()\n---\n",
+    );
+    write(&dir, "nested/b.test", "====\n>>> other.whiley\n---\n");
+    write(&dir, "ignored.txt", "garbage");
+    let out = wtf(&["check", dir.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_check_02() {
+    let dir = temp_dir("cli_check_02");
+    let file = write(&dir, "a.test", "====\n>>> main.whiley 1:x\n");
+    let out = wtf(&["check", file.to_str().unwrap()]);
+    // Exit code for ErrorKind::InvalidRange
+    assert_eq!(out.status.code(), Some(16));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_check_03() {
+    let dir = temp_dir("cli_check_03");
+    let file = write(&dir, "a.test", "====\n<<< main.whiley\n");
+    let out = wtf(&["check", file.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(4));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_check_04() {
+    let out = wtf(&["check", "does/not/exist.test"]);
+    assert_eq!(out.status.code(), Some(3));
+}
+
+#[test]
+fn cli_fmt_01() {
+    let dir = temp_dir("cli_fmt_01");
+    let file = write(&dir, "a.test", "b = 1\na = 2\n====\n>>> main.whiley\n");
+    let path = file.to_str().unwrap();
+    let out = wtf(&["fmt", "--check", path]);
+    assert_eq!(out.status.code(), Some(1));
+    let out = wtf(&["fmt", path]);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "a = 2\nb = 1\n================\n>>> main.whiley\n---\n"
+    );
+    let out = wtf(&["fmt", "--check", path]);
+    assert_eq!(out.status.code(), Some(0));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_fmt_02() {
+    // Lines after the end of a frame are never dropped
+    let dir = temp_dir("cli_fmt_02");
+    let input = "====\n>>> main.whiley\nx\n---\nE101 main.whiley 1,0\n>>> other.whiley\ny\n";
+    let file = write(&dir, "a.test", input);
+    let path = file.to_str().unwrap();
+    // Exit code for ErrorKind::UnexpectedLine
+    assert_eq!(wtf(&["check", path]).status.code(), Some(22));
+    assert_eq!(wtf(&["fmt", path]).status.code(), Some(22));
+    assert_eq!(fs::read_to_string(&file).unwrap(), input);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_fmt_03() {
+    // Line terminators of control lines follow the first line
+    let dir = temp_dir("cli_fmt_03");
+    let input = "b = 1\r\n====\r\n>>> main.whiley\r\nskip\r\nlast\r\n\\ No newline at end of file\r\n---\r\nE1 main.whiley 1,0\r\n";
+    let file = write(&dir, "a.test", input);
+    let path = file.to_str().unwrap();
+    assert_eq!(wtf(&["fmt", path]).status.code(), Some(0));
+    let output = fs::read_to_string(&file).unwrap();
+    assert_eq!(
+        output,
+        "b = 1\r\n================\r\n>>> main.whiley\r\nskip\r\nlast\r\n\\ No newline at end of file\r\n---\r\nE1 main.whiley 1,0\r\n"
+    );
+    assert_eq!(
+        WhileyTestFile::new(&output).unwrap(),
+        WhileyTestFile::new(input).unwrap()
+    );
+    assert_eq!(wtf(&["fmt", "--check", path]).status.code(), Some(0));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_dump_01() {
+    let dir = temp_dir("cli_dump_01");
+    let file = write(
+        &dir,
+        "a.test",
+        "x = true\n====\n>>> main.whiley\na\nb\n---\nE101 main.whiley 1,2\n====\n<<< main.whiley\n",
+    );
+    let out = wtf(&["dump", file.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "config:\n    x = true\nframe 0:\n    create main.whiley (2 lines)\n    marker E101 main.whiley 1,2\nframe 1:\n    remove main.whiley\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_stats_01() {
+    let dir = temp_dir("cli_stats_01");
+    write(&dir, "a.test", "====\n>>> a.whiley\n---\nE1 a.whiley 1,1\n");
+    write(
+        &dir,
+        "b.test",
+        "====\n>>> b.whiley\n====\n>>> b.whiley 1\n---\nW2 b.whiley 1,1\n",
+    );
+    let out = wtf(&["stats", dir.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("files:   2\n"));
+    assert!(stdout.contains("frames:  3\n"));
+    assert!(stdout.contains("actions: 3 (create 2, insert 1, remove 0)\n"));
+    assert!(stdout.contains("markers: 2 (error 1, warning 1, other 0)\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_bless_01() {
+    let dir = temp_dir("cli_bless_01");
+    let file = write(
+        &dir,
+        "a.test",
+        "====\n>>> main.whiley\nskip\n---\nE101 main.whiley 1,0\n====\n<<< main.whiley\n",
+    );
+    let actual = write(
+        &dir,
+        "actual.txt",
+        "====\n---\nE102 main.whiley 1,0\n====\n",
+    );
+    let out = wtf(&["bless", file.to_str().unwrap(), actual.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "frame 0: -E101 main.whiley 1,0\nframe 0: +E102 main.whiley 1,0\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "====\n>>> main.whiley\nskip\n---\nE102 main.whiley 1,0\n====\n<<< main.whiley\n"
+    );
+    // Mismatched number of frames
+    let actual = write(&dir, "actual.txt", "====\n");
+    let out = wtf(&["bless", file.to_str().unwrap(), actual.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_run_01() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = temp_dir("cli_run_01");
+    let wyc = write(
+        &dir,
+        "wyc.sh",
+        "#!/bin/sh\necho 'main.whiley:1: unknown variable'\necho 'x'\necho '^'\n",
+    );
+    fs::set_permissions(&wyc, fs::Permissions::from_mode(0o755)).unwrap();
+    let messages = write(
+        &dir,
+        "messages.txt",
+        "# Known messages\n201 unknown variable\n",
+    );
+    let pass = write(
+        &dir,
+        "a.test",
+        "====\n>>> main.whiley\nx\n---\nE201 main.whiley 1,0\n",
+    );
+    let fail = write(&dir, "b.test", "====\n>>> main.whiley\nx\n");
+    let run = |file: &Path| {
+        wtf(&[
+            "run",
+            "--messages",
+            messages.to_str().unwrap(),
+            file.to_str().unwrap(),
+            "--",
+            wyc.to_str().unwrap(),
+        ])
+    };
+    let out = run(&pass);
+    assert_eq!(out.status.code(), Some(0));
+    let out = run(&fail);
+    assert_eq!(out.status.code(), Some(5));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("frame 0: fail"));
+    assert!(stdout.contains("+ E201 main.whiley 1,0 (unexpected)"));
+    // Results in other formats
+    let mut args = vec!["run", "--format", "tap", fail.to_str().unwrap()];
+    args.extend(["--", wyc.to_str().unwrap()]);
+    let out = wtf(&args);
+    assert_eq!(out.status.code(), Some(5));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("TAP version 13\n1..1\nnot ok 1 - "));
+    args[2] = "junit";
+    let stdout = String::from_utf8(wtf(&args).stdout).unwrap();
+    assert!(stdout.contains(r#"<testsuites tests="1" failures="0" errors="1""#));
+    args[2] = "xml";
+    assert_eq!(wtf(&args).status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_run_02() {
+    for timeout in ["-1", "inf", "NaN", "x"] {
+        let out = wtf(&["run", "--timeout", timeout, "a.test", "--", "wyc"]);
+        assert_eq!(out.status.code(), Some(2));
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(stderr.contains(&format!("invalid timeout \"{}\"", timeout)));
+    }
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn wtf(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wtf"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn write(dir: &Path, filename: &str, contents: &str) -> PathBuf {
+    let path = dir.join(filename);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    path
+}
@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::PathBuf;
+use whiley_test_file::diagnostics::{DiagnosticErrorKind, DiagnosticParser, TextParser};
+use whiley_test_file::WhileyTestFile;
+
+pub static CORPUS_DIR: &str = "tests/diagnostics";
+
+// ===============================================================
+// Text Tests
+// ===============================================================
+
+#[test]
+fn text_empty() {
+    check(&text_parser(), "empty.out");
+}
+
+#[test]
+fn text_multiple() {
+    check(&text_parser(), "multiple.out");
+}
+
+#[test]
+fn text_tabs() {
+    check(&text_parser(), "tabs.out");
+}
+
+#[test]
+fn text_type_mismatch() {
+    check(&text_parser(), "type_mismatch.out");
+}
+
+#[test]
+fn text_invalid_01() {
+    let output = "main.whiley:1: division by zero\nx / 0\n  ^\n";
+    let err = text_parser().parse(output).err().unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::UnknownMessage);
+    assert_eq!(err.line, 1);
+}
+
+#[test]
+fn text_invalid_02() {
+    let output = "Compiling\nmain.whiley:3: unknown variable x\nreturn x\n";
+    let err = text_parser().parse(output).err().unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::MissingCaret);
+    assert_eq!(err.line, 2);
+}
+
+#[test]
+fn text_invalid_03() {
+    let output = "/tmp/main.whiley:1: unknown variable x\nx\n^\n";
+    let err = text_parser().parse(output).err().unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::InvalidFilename);
+    assert_eq!(err.line, 1);
+}
+
+// ===============================================================
+// JSON Tests
+// ===============================================================
+
+#[cfg(feature = "json")]
+#[test]
+fn json_multiple() {
+    check(&whiley_test_file::diagnostics::JsonParser, "multiple.json");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_type_mismatch() {
+    check(
+        &whiley_test_file::diagnostics::JsonParser,
+        "type_mismatch.json",
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_invalid_01() {
+    let err = whiley_test_file::diagnostics::JsonParser
+        .parse("[{ \"file\": \"main.whiley\" }]")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::InvalidJson);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_invalid_02() {
+    let output = r#"[{ "file": "../main.whiley", "line": 1, "start": 0, "end": 0, "code": 101 }]"#;
+    let err = whiley_test_file::diagnostics::JsonParser
+        .parse(output)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::InvalidFilename);
+    assert_eq!(err.text, "../main.whiley");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_invalid_03() {
+    let output = r#"[{ "file": "main.whiley", "line": 1, "start": 0, "end": 0, "code": 101, "severity": "note" }]"#;
+    let err = whiley_test_file::diagnostics::JsonParser
+        .parse(output)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, DiagnosticErrorKind::InvalidJson);
+    assert_eq!(err.text, "unknown severity \"note\"");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_severity() {
+    let output = r#"[
+        { "file": "a", "line": 1, "start": 0, "end": 0, "code": 1 },
+        { "file": "a", "line": 1, "start": 0, "end": 0, "code": 2, "severity": "error" },
+        { "file": "a", "line": 1, "start": 0, "end": 0, "code": 3, "severity": "warning" },
+        { "file": "a", "line": 1, "start": 0, "end": 0, "code": 4, "severity": "info" }
+    ]"#;
+    let markers = whiley_test_file::diagnostics::JsonParser
+        .parse(output)
+        .unwrap();
+    let severities: String = markers.iter().map(|m| m.severity.to_char()).collect();
+    assert_eq!(severities, "EEWI");
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+/// The messages (and their error codes) used in the corpus.
+fn text_parser() -> TextParser {
+    TextParser::new()
+        .message("expected type", 400)
+        .message("unknown variable", 201)
+        .message("unreachable code", 501)
+        .message("missing return statement", 304)
+}
+
+/// Parse a recorded compiler output from the corpus, and check the
+/// markers produced match those recorded alongside it.
+fn check<P: DiagnosticParser>(parser: &P, name: &str) {
+    let path = PathBuf::from(CORPUS_DIR).join(name);
+    let output = fs::read_to_string(&path).unwrap();
+    let markers = parser.parse(&output).unwrap();
+    // Parse expected markers
+    let expected =
+        fs::read_to_string(path.with_file_name(format!("{}.markers", stem(name)))).unwrap();
+    let expected = format!("====\n---\n{}", expected);
+    let expected = WhileyTestFile::new(&expected).unwrap();
+    assert_eq!(markers, expected.frame(0).markers);
+}
+
+/// Determine the name of the markers file for a given output file
+/// (e.g. `multiple.out` gives `multiple`, whilst `multiple.json` gives
+/// `multiple.json`).
+fn stem(name: &str) -> &str {
+    name.strip_suffix(".out").unwrap_or(name)
+}
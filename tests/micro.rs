@@ -1,4 +1,16 @@
-use whiley_test_file::{Action, Coordinate, Error, Range, Value, WhileyTestFile};
+#![allow(clippy::len_zero, clippy::let_unit_value)]
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use whiley_test_file::options::OptionErrorKind;
+use whiley_test_file::syntax::{MarkerChange, SyntaxTree};
+use whiley_test_file::{
+    compare, Action, BuildError, Change, ColumnUnit, Columns, Coordinate, ErrorKind, Frame, Item,
+    Marker, Materialiser, NearMiss, PathErrorKind, Range, Severity, Snapshot, SnapshotError,
+    TestPath, Value, Violation, ViolationKind, WhileyTestFile, WhileyTestFileBuf,
+    WhileyTestFileBuilder,
+};
 
 // ===============================================================
 // Config Tests
@@ -31,52 +43,52 @@ fn config_bool_02() {
 
 #[test]
 fn config_string_01() {
-    parse_config_option("s = \"world\"", "s", Value::String("world"));
+    parse_config_option("s = \"world\"", "s", Value::String("world".into()));
 }
 
 #[test]
 fn config_invalid_01() {
-    let _wtf = parse_expecting("hello ", Error::InvalidConfigOption);
+    let _wtf = parse_expecting("hello ", ErrorKind::InvalidConfigOption);
 }
 
 #[test]
 fn config_invalid_02() {
-    let _wtf = parse_expecting("hello = ", Error::InvalidConfigValue);
+    let _wtf = parse_expecting("hello = ", ErrorKind::InvalidConfigValue);
 }
 
 #[test]
 fn config_invalid_03() {
-    parse_expecting("hello = t", Error::InvalidConfigValue);
+    parse_expecting("hello = t", ErrorKind::InvalidConfigValue);
 }
 
 #[test]
 fn config_int_invalid_01() {
-    parse_expecting("hello = 1c", Error::InvalidIntValue);
+    parse_expecting("hello = 1c", ErrorKind::InvalidIntValue);
 }
 
 #[test]
 fn config_int_invalid_02() {
-    parse_expecting("hello = -1c", Error::InvalidIntValue);
+    parse_expecting("hello = -1c", ErrorKind::InvalidIntValue);
 }
 
 #[test]
 fn config_string_invalid_01() {
-    parse_expecting("hello = \"", Error::InvalidStringValue);
+    parse_expecting("hello = \"", ErrorKind::InvalidStringValue);
 }
 
 #[test]
 fn config_string_invalid_02() {
-    parse_expecting("hello = \"x", Error::InvalidStringValue);
+    parse_expecting("hello = \"x", ErrorKind::InvalidStringValue);
 }
 
 #[test]
 fn config_string_invalid_03() {
-    parse_expecting("hello = \"\"\"", Error::InvalidStringValue);
+    parse_expecting("hello = \"\"\"", ErrorKind::InvalidStringValue);
 }
 
 #[test]
 fn config_string_invalid_04() {
-    parse_expecting("hello = \"x\"x\"", Error::InvalidStringValue);
+    parse_expecting("hello = \"x\"x\"", ErrorKind::InvalidStringValue);
 }
 
 // ===============================================================
@@ -96,7 +108,7 @@ type nat is (int x)"#,
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::CREATE(_, _)));
+    assert!(matches!(a0, Action::CREATE(_, _, _)));
     assert!(a0.lines().len() == 1);
     assert!(a0.lines()[0] == "type nat is (int x)");
 }
@@ -115,7 +127,7 @@ where x >= 0"#,
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::CREATE(_, _)));
+    assert!(matches!(a0, Action::CREATE(_, _, _)));
     assert!(a0.lines().len() == 2);
     assert!(a0.lines()[0] == "type nat is (int x)");
     assert!(a0.lines()[1] == "where x >= 0");
@@ -137,7 +149,7 @@ type uint is (int y)"#,
     assert!(f0.actions.len() == 2);
     //
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::CREATE(_, _)));
+    assert!(matches!(a0, Action::CREATE(_, _, _)));
     assert!(a0.lines().len() == 1);
     assert!(a0.lines()[0] == "type nat is (int x)");
     //
@@ -185,7 +197,7 @@ type nat is (int x)"#,
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::INSERT(_, _, _)));
+    assert!(matches!(a0, Action::INSERT(_, _, _, _)));
     assert!(a0.range() == &Range(0, 0));
     assert!(a0.lines().len() == 1);
 }
@@ -198,7 +210,7 @@ fn single_frame_07() {
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::INSERT(_, _, _)));
+    assert!(matches!(a0, Action::INSERT(_, _, _, _)));
     assert!(a0.range() == &Range(0, 0));
     assert!(a0.lines().len() == 1);
 }
@@ -216,7 +228,7 @@ type nat is (int x)"#,
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::INSERT(_, _, _)));
+    assert!(matches!(a0, Action::INSERT(_, _, _, _)));
     assert!(a0.range() == &Range(0, 1));
     assert!(a0.lines().len() == 1);
 }
@@ -235,7 +247,7 @@ type nat is (int x)
     let f0 = wtf.frame(0);
     assert!(f0.actions.len() == 1);
     let a0 = &f0.actions[0];
-    assert!(matches!(a0, Action::CREATE(_, _)));
+    assert!(matches!(a0, Action::CREATE(_, _, _)));
     assert!(a0.lines().len() == 1);
 }
 
@@ -326,6 +338,72 @@ E303 main.whiley 1,5 "#,
     assert!(m0.location == Coordinate(1, Range(5, 5)));
 }
 
+#[test]
+fn single_frame_14() {
+    // Markers with different severities
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+type nat is (int x)
+---
+E101 main.whiley 1,1
+W101 main.whiley 1,1
+I7 main.whiley 1,1
+X8 main.whiley 1,1"#,
+    );
+    let f0 = wtf.frame(0);
+    assert!(f0.markers.len() == 4);
+    assert!(f0.markers[0].severity == Severity::Error);
+    assert!(f0.markers[1].severity == Severity::Warning);
+    assert!(f0.markers[2].severity == Severity::Info);
+    assert!(f0.markers[3].severity == Severity::Unknown('X'));
+    assert!(f0.markers[0].errno == f0.markers[1].errno);
+    assert!(f0.markers[0] != f0.markers[1]);
+}
+
+#[test]
+fn single_frame_15() {
+    // Header and marker fields separated by any whitespace
+    let wtf = parse(
+        "====\n>>>  main.whiley\t1:2\nskip\n<<<\tother.whiley\n---\nE101\t main.whiley  1,2:3",
+    );
+    let f0 = wtf.frame(0);
+    assert_eq!(
+        f0.actions[0],
+        Action::INSERT(path("main.whiley"), Range(1, 2), vec!["skip".into()], true)
+    );
+    assert_eq!(f0.actions[1], Action::REMOVE(path("other.whiley")));
+    assert_eq!(f0.markers[0].filename, "main.whiley");
+    assert_eq!(f0.markers[0].location, Coordinate(1, Range(2, 3)));
+}
+
+#[test]
+fn single_frame_16() {
+    // Quoted filenames
+    let wtf = parse(
+        r#"
+====
+>>> "my file.whiley"
+skip
+>>> "a \"b\" \\c.whiley" 1
+<<< "d.whiley"
+---
+E101 "my file.whiley" 1,2"#,
+    );
+    let f0 = wtf.frame(0);
+    assert_eq!(f0.actions[0].filename(), "my file.whiley");
+    // Backslashes separate path components
+    assert_eq!(f0.actions[1].filename(), r#"a "b" /c.whiley"#);
+    assert_eq!(f0.actions[2].filename(), "d.whiley");
+    assert_eq!(f0.markers[0].filename, "my file.whiley");
+    // Quoted filenames without escapes are borrowed
+    assert!(matches!(
+        f0.markers[0].filename.clone().into_inner(),
+        Cow::Borrowed(_)
+    ));
+}
+
 #[test]
 fn single_frame_invalid_01() {
     // Frame with invalid insert action
@@ -334,7 +412,7 @@ fn single_frame_invalid_01() {
 ====
 >>>
 type nat is (int x)"#,
-        Error::InvalidAction,
+        ErrorKind::InvalidAction,
     );
 }
 
@@ -346,7 +424,7 @@ fn single_frame_invalid_02() {
 ====
 >>> 1 2 3
 type nat is (int x)"#,
-        Error::InvalidAction,
+        ErrorKind::InvalidAction,
     );
 }
 
@@ -358,7 +436,7 @@ fn single_frame_invalid_03() {
 ====
 <<<
 type nat is (int x)"#,
-        Error::InvalidAction,
+        ErrorKind::InvalidAction,
     );
 }
 
@@ -370,7 +448,7 @@ fn single_frame_invalid_04() {
 ====
 <<< 1 2 3
 type nat is (int x)"#,
-        Error::InvalidAction,
+        ErrorKind::InvalidAction,
     );
 }
 
@@ -382,7 +460,7 @@ fn single_frame_invalid_05() {
 ====
 >>> main.whiley x
 type nat is (int x)"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -394,7 +472,7 @@ fn single_frame_invalid_06() {
 ====
 >>> main.whiley 1-
 type nat is (int x)"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -406,7 +484,7 @@ fn single_frame_invalid_07() {
 ====
 >>> main.whiley 1:
 type nat is (int x)"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -418,7 +496,7 @@ fn single_frame_invalid_08() {
 ====
 >>> main.whiley 1:x
 type nat is (int x)"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -432,7 +510,7 @@ fn single_frame_invalid_09() {
 type nat is (int x)
 ---
 E303"#,
-        Error::InvalidMarker,
+        ErrorKind::InvalidMarker,
     );
 }
 
@@ -446,7 +524,7 @@ fn single_frame_invalid_10() {
 type nat is (int x)
 ---
 E303 main.whiley"#,
-        Error::InvalidMarker,
+        ErrorKind::InvalidMarker,
     );
 }
 
@@ -460,7 +538,7 @@ fn single_frame_invalid_11() {
 type nat is (int x)
 ---
 E303 main.whiley 0,1:2 abc"#,
-        Error::InvalidMarker,
+        ErrorKind::InvalidMarker,
     );
 }
 
@@ -474,7 +552,35 @@ fn single_frame_invalid_12() {
 type nat is (int x)
 ---
 Exx main.whiley 1,5:7"#,
-        Error::InvalidErrorCode,
+        ErrorKind::InvalidErrorCode,
+    );
+}
+
+#[test]
+fn single_frame_invalid_19() {
+    // Frame with marker missing severity
+    parse_expecting(
+        r#"
+====
+>>> main.whiley
+type nat is (int x)
+---
+1303 main.whiley 1,5:7"#,
+        ErrorKind::InvalidErrorCode,
+    );
+}
+
+#[test]
+fn single_frame_invalid_20() {
+    // Frame with marker missing code
+    parse_expecting(
+        r#"
+====
+>>> main.whiley
+type nat is (int x)
+---
+ main.whiley 1,5:7 x"#,
+        ErrorKind::InvalidErrorCode,
     );
 }
 
@@ -488,7 +594,7 @@ fn single_frame_invalid_13() {
 type nat is (int x)
 ---
 E303 main.whiley 1:5:7"#,
-        Error::InvalidCoordinate,
+        ErrorKind::InvalidCoordinate,
     );
 }
 
@@ -502,7 +608,7 @@ fn single_frame_invalid_14() {
 type nat is (int x)
 ---
 E303 main.whiley 1,5,7"#,
-        Error::InvalidCoordinate,
+        ErrorKind::InvalidCoordinate,
     );
 }
 
@@ -516,7 +622,7 @@ fn single_frame_invalid_15() {
 type nat is (int x)
 ---
 E303 main.whiley x,5:7"#,
-        Error::InvalidCoordinate,
+        ErrorKind::InvalidCoordinate,
     );
 }
 
@@ -530,7 +636,7 @@ fn single_frame_invalid_16() {
 type nat is (int x)
 ---
 E303 main.whiley 1,x:7"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -544,7 +650,7 @@ fn single_frame_invalid_17() {
 type nat is (int x)
 ---
 E303 main.whiley 1,1:"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
     );
 }
 
@@ -558,10 +664,45 @@ fn single_frame_invalid_18() {
 type nat is (int x)
 ---
 E303 main.whiley 1,1:x"#,
-        Error::InvalidRange,
+        ErrorKind::InvalidRange,
+    );
+}
+
+#[test]
+fn single_frame_invalid_21() {
+    // Unterminated quote
+    parse_expecting("====\n>>> \"main.whiley\nskip", ErrorKind::InvalidFilename);
+}
+
+#[test]
+fn single_frame_invalid_22() {
+    // Invalid escape
+    parse_expecting(
+        "====\n---\nE101 \"main\\n.whiley\" 1,2",
+        ErrorKind::InvalidFilename,
     );
 }
 
+#[test]
+fn single_frame_invalid_23() {
+    // Text following closing quote
+    parse_expecting("====\n<<< \"main\".whiley", ErrorKind::InvalidFilename);
+}
+
+#[test]
+fn single_frame_invalid_24() {
+    // Unknown action kinds
+    parse_expecting("====\n>>>> main.whiley", ErrorKind::InvalidAction);
+    parse_expecting("====\n>>>x main.whiley", ErrorKind::InvalidAction);
+    parse_expecting("====\n<<<< main.whiley", ErrorKind::InvalidAction);
+}
+
+#[test]
+fn single_frame_invalid_25() {
+    // Remove action with range
+    parse_expecting("====\n<<< main.whiley 1:2", ErrorKind::InvalidAction);
+}
+
 // ===============================================================
 // Multi Frame Tests
 // ===============================================================
@@ -776,50 +917,1740 @@ type uint is (int x)"#,
 }
 
 // ===============================================================
-// Helpers
+// Error Position Tests
 // ===============================================================
 
-fn parse(input: &str) -> WhileyTestFile {
-    // Parser test file
-    let wtf = WhileyTestFile::new(input);
-    // Assume parsing succeeded
-    assert!(wtf.is_ok());
-    // Done
-    wtf.unwrap()
+#[test]
+fn error_position_01() {
+    let err = WhileyTestFile::new("x = 1\nhello = 1c").err().unwrap();
+    assert!(err.kind == ErrorKind::InvalidIntValue);
+    assert!(err.line == 2);
+    assert!(err.span == (8..10));
+    assert!(err.text == "hello = 1c");
+    assert!(err.token() == "1c");
 }
 
-fn parse_config_option(input: &str, key: &str, val: Value) {
-    // Parser test file
-    let wtf = WhileyTestFile::new(input).unwrap();
-    // Look for value
-    match wtf.get(key) {
-        Some(v) => {
-            if &val != v {
-                panic!(
-                    "Expecting config value {:?} for key {:?}, got {:?}!",
-                    val, key, v
-                );
-            }
-        }
-        _ => {
-            panic!(
-                "Expected config value {:?} for key {:?}, got nothing!",
-                val, key
-            );
-        }
-    }
+#[test]
+fn error_position_02() {
+    let err = WhileyTestFile::new(
+        r#"====
+>>> main.whiley 1:x
+type nat is (int x)"#,
+    )
+    .err()
+    .unwrap();
+    assert!(err.kind == ErrorKind::InvalidRange);
+    assert!(err.line == 2);
+    assert!(err.token() == "x");
+    assert!(err.hint.is_some());
 }
 
-fn parse_expecting(input: &str, expected: Error) {
-    // Parser test file
-    match WhileyTestFile::new(input) {
-        Ok(_) => {
-            panic!("File should not have parsed!");
-        }
-        Err(err) => {
-            if err != expected {
-                panic!("Expected error {:?}, got {:?}!", expected, err);
-            }
-        }
-    }
+#[test]
+fn error_position_03() {
+    let err = WhileyTestFile::new(
+        r#"====
+>>> main.whiley
+type nat is (int x)
+---
+E101 main.whiley 1,1
+Exx main.whiley 1,5:7"#,
+    )
+    .err()
+    .unwrap();
+    assert!(err.kind == ErrorKind::InvalidErrorCode);
+    assert!(err.line == 6);
+    assert!(err.span == (0..3));
+}
+
+#[test]
+fn error_position_04() {
+    let err = WhileyTestFile::new(
+        r#"====
+<<< 1 2 3"#,
+    )
+    .err()
+    .unwrap();
+    assert!(err.kind == ErrorKind::InvalidAction);
+    assert!(err.token() == "<<< 1 2 3");
+    let msg = err.to_string();
+    assert!(msg.starts_with("line 2, column 1: invalid action `<<< 1 2 3`"));
+}
+
+#[test]
+fn error_position_05() {
+    let err = WhileyTestFile::new("====\n---\nE101  \"a b\"x 1,2")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, ErrorKind::InvalidFilename);
+    assert_eq!(err.span, 6..12);
+    assert_eq!(err.token(), "\"a b\"x");
+}
+
+#[test]
+fn error_position_06() {
+    // Columns count characters, not bytes
+    let err = WhileyTestFile::new("====\n>>> é.whiley x").err().unwrap();
+    assert_eq!(err.kind, ErrorKind::InvalidRange);
+    assert_eq!(err.span, 14..15);
+    assert_eq!(err.column(), 14);
+    assert!(err
+        .to_string()
+        .starts_with("line 2, column 14: invalid range `x`"));
+}
+
+#[test]
+fn error_position_07() {
+    let err = WhileyTestFile::new("====\n>>>x main.whiley").err().unwrap();
+    assert_eq!(err.kind, ErrorKind::InvalidAction);
+    assert_eq!(err.token(), ">>>x");
+    let err = WhileyTestFile::new("====\n<<< main.whiley 1:2")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, ErrorKind::InvalidAction);
+    assert_eq!(err.token(), "1:2");
+}
+
+// ===============================================================
+// Recovery Tests
+// ===============================================================
+
+#[test]
+fn recovery_01() {
+    let (wtf, errs) = WhileyTestFile::new_recovering(
+        r#"x = 1
+y = 1c
+z = true
+====
+>>> main.whiley
+type nat is (int x)"#,
+    );
+    assert!(errs.len() == 1);
+    assert!(errs[0].kind == ErrorKind::InvalidIntValue);
+    assert!(errs[0].line == 2);
+    assert!(wtf.get_int("x") == Some(1));
+    assert!(wtf.get_bool("z") == Some(true));
+    assert!(wtf.size() == 1);
+}
+
+#[test]
+fn recovery_02() {
+    let (wtf, errs) = WhileyTestFile::new_recovering(
+        r#"====
+>>> main.whiley x
+type nat is (int x)
+>>> other.whiley
+type uint is (int y)
+---
+E101 main.whiley
+E102 other.whiley 1,2
+Exx other.whiley 1,2
+====
+<<< 1 2 3
+"#,
+    );
+    assert!(errs.len() == 4);
+    assert!(errs[0].kind == ErrorKind::InvalidRange);
+    assert!(errs[0].line == 2);
+    assert!(errs[1].kind == ErrorKind::InvalidMarker);
+    assert!(errs[1].line == 7);
+    assert!(errs[2].kind == ErrorKind::InvalidErrorCode);
+    assert!(errs[2].line == 9);
+    assert!(errs[3].kind == ErrorKind::InvalidAction);
+    assert!(errs[3].line == 11);
+    assert!(wtf.size() == 2);
+    let f0 = wtf.frame(0);
+    assert!(f0.actions.len() == 1);
+    assert!(
+        f0.actions[0]
+            == Action::CREATE(
+                path("other.whiley"),
+                vec!["type uint is (int y)".into()],
+                true
+            )
+    );
+    assert!(f0.markers.len() == 1);
+    assert!(f0.markers[0].errno == 102);
+    assert!(wtf.frame(1).actions.is_empty());
+}
+
+#[test]
+fn recovery_03() {
+    let (wtf, errs) = WhileyTestFile::new_recovering(
+        r#"====
+>>> main.whiley
+type nat is (int x)"#,
+    );
+    assert!(errs.is_empty());
+    assert!(wtf.size() == 1);
+}
+
+#[test]
+fn recovery_04() {
+    // Lines which cannot follow the end of a frame
+    let input = "====\n>>> main.whiley\nx\n---\nE101 main.whiley 1,0\n>>> other.whiley\ny\n---\n====\n<<< main.whiley\n";
+    let (wtf, errs) = WhileyTestFile::new_recovering(input);
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].kind, ErrorKind::UnexpectedLine);
+    assert_eq!(errs[0].line, 6);
+    assert_eq!(errs[0].token(), ">>> other.whiley");
+    assert_eq!(wtf.size(), 2);
+    assert_eq!(wtf.frame(0).actions.len(), 1);
+    assert_eq!(wtf.frame(1).actions[0], Action::REMOVE(path("main.whiley")));
+    parse_expecting(input, ErrorKind::UnexpectedLine);
+    parse_expecting("====\n---\n---\n", ErrorKind::UnexpectedLine);
+}
+
+// ===============================================================
+// Printer Tests
+// ===============================================================
+
+#[test]
+fn printer_01() {
+    check_roundtrip("x = 1\ny = \"hello\"\nz = false");
+}
+
+#[test]
+fn printer_02() {
+    check_roundtrip(
+        r#"
+====
+>>> main.whiley
+type nat is (int x)
+where x >= 0
+---
+E101 main.whiley 1,2
+E302 main.whiley 2,2:3"#,
+    );
+}
+
+#[test]
+fn printer_03() {
+    check_roundtrip(
+        r#"whiley.verify = false
+boogie.timeout = 1000
+================
+>>> main.whiley
+method main():
+>>> other.whiley
+import main
+---
+E101 main.whiley 1,2
+W302 main.whiley 2,2:3
+================
+<<< other.whiley
+>>> main.whiley 1:1
+method main()
+    skip
+>>> main.whiley 3
+>>> main.whiley 0:2
+
+---
+"#,
+    );
+}
+
+#[test]
+fn printer_04() {
+    let wtf = parse(
+        r#"b = 1
+a = true
+====
+<<< other.whiley
+>>> main.whiley 1:1
+skip
+---
+E101 main.whiley 1,2:2"#,
+    );
+    let expected = r#"a = true
+b = 1
+================
+<<< other.whiley
+>>> main.whiley 1
+skip
+---
+E101 main.whiley 1,2
+"#;
+    assert_eq!(wtf.to_string(), expected);
+    let mut bytes = Vec::new();
+    wtf.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes, expected.as_bytes());
+}
+
+#[test]
+fn printer_05() {
+    // Content lines which look like control lines are escaped
+    let wtf = parse(
+        r#"====
+>>> main.whiley
+\=== section ===
+\---
+\>>> x
+\<<< y
+\\---
+\n
+---
+E101 main.whiley 1,2"#,
+    );
+    assert_eq!(
+        wtf.frame(0).actions[0].lines(),
+        ["=== section ===", "---", ">>> x", "<<< y", "\\---", "\\n"]
+    );
+    assert_eq!(wtf.frame(0).markers.len(), 1);
+    assert_eq!(
+        wtf.to_string(),
+        r#"================
+>>> main.whiley
+\=== section ===
+\---
+\>>> x
+\<<< y
+\\---
+\n
+---
+E101 main.whiley 1,2
+"#
+    );
+    check_roundtrip(&wtf.to_string());
+}
+
+#[test]
+fn printer_06() {
+    // Arbitrary content round-trips
+    let lines = ["---", "\\---", "\\\\===", "\\", "\\\\", "-- x", "\\x"];
+    let wtf = WhileyTestFileBuilder::new()
+        .frame(|f| {
+            f.create("main.whiley", lines)
+                .insert("main.whiley", Range(1, 1), lines)
+        })
+        .build()
+        .unwrap();
+    let output = wtf.to_string();
+    assert_eq!(parse(&output), wtf);
+    check_lossless(&output);
+}
+
+#[test]
+fn printer_07() {
+    // Filenames are quoted when necessary
+    let wtf = WhileyTestFileBuilder::new()
+        .frame(|f| {
+            f.create("my file.whiley", ["skip"])
+                .create(r#""a\b".whiley"#, ["skip"])
+                .create(r#"c\d"e.whiley"#, ["skip"])
+                .expect_error(101, "my file.whiley", Coordinate(1, Range(0, 0)))
+        })
+        .build()
+        .unwrap();
+    let output = wtf.to_string();
+    assert_eq!(
+        output,
+        r#"================
+>>> "my file.whiley"
+skip
+>>> "\"a/b\".whiley"
+skip
+>>> c/d"e.whiley
+skip
+---
+E101 "my file.whiley" 1,0
+"#
+    );
+    assert_eq!(parse(&output), wtf);
+}
+
+// ===============================================================
+// Syntax Tree Tests
+// ===============================================================
+
+#[test]
+fn syntax_01() {
+    check_lossless("");
+}
+
+#[test]
+fn syntax_02() {
+    check_lossless("b = 1  \n\na = true\n====\n>>> main.whiley\ntype nat is (int x)\n");
+}
+
+#[test]
+fn syntax_03() {
+    check_lossless(
+        "x = 1\r\n================\r\n>>> main.whiley\r\n\r\n---\r\nE101 main.whiley 1,2",
+    );
+}
+
+#[test]
+fn syntax_04() {
+    check_lossless(
+        r#"
+====
+>>> main.whiley
+method main():
+---
+E101 main.whiley 1,2
+
+====
+<<< main.whiley
+---
+stray text"#,
+    );
+}
+
+#[test]
+fn syntax_05() {
+    let input = "b = 1\na = false\n\n====\n>>> main.whiley\nskip";
+    let mut tree = SyntaxTree::new(input);
+    tree.set_config("a", &Value::Bool(true));
+    tree.set_config("c", &Value::String("x".into()));
+    assert_eq!(
+        tree.to_string(),
+        "b = 1\na = true\nc = \"x\"\n\n====\n>>> main.whiley\nskip"
+    );
+    assert!(tree.remove_config("b"));
+    assert!(!tree.remove_config("b"));
+    assert_eq!(
+        tree.to_string(),
+        "a = true\nc = \"x\"\n\n====\n>>> main.whiley\nskip"
+    );
+}
+
+#[test]
+fn syntax_06() {
+    let input =
+        "====\r\n>>> main.whiley\r\nskip\r\n---\r\nE101 main.whiley 1,2:2 \r\nE102 main.whiley 1,3";
+    let mut tree = SyntaxTree::new(input);
+    let wtf = parse("====\n---\nE101 main.whiley 1,2\nE103 main.whiley 2,3");
+    tree.set_markers(0, &wtf.frame(0).markers);
+    assert_eq!(
+        tree.to_string(),
+        "====\r\n>>> main.whiley\r\nskip\r\n---\r\nE101 main.whiley 1,2:2 \r\nE103 main.whiley 2,3"
+    );
+}
+
+#[test]
+fn syntax_07() {
+    let mut tree = SyntaxTree::new("====\n>>> main.whiley\nskip");
+    let wtf = parse("====\n---\nE101 main.whiley 1,2");
+    tree.set_markers(0, &wtf.frame(0).markers);
+    assert_eq!(
+        tree.to_string(),
+        "====\n>>> main.whiley\nskip\n---\nE101 main.whiley 1,2"
+    );
+    assert_eq!(tree.parse().unwrap().frame(0).markers, wtf.frame(0).markers);
+}
+
+#[test]
+fn syntax_08() {
+    let tree = SyntaxTree::new("x = 1\n====\n>>> main.whiley 1:x\n");
+    let err = tree.parse().err().unwrap();
+    assert!(err.kind == ErrorKind::InvalidRange);
+    assert!(err.line == 3);
+}
+
+#[test]
+fn syntax_09() {
+    let input = "====\n>>> main.whiley\nskip\n---\nE101 main.whiley 1,2\nE102 main.whiley 1,3\n";
+    let mut tree = SyntaxTree::new(input);
+    let wtf = parse("====\n---\nE103 main.whiley 1,4\nE102 main.whiley 1,3\nE101 main.whiley 1,2");
+    let changes = tree.set_markers(0, &wtf.frame(0).markers);
+    assert_eq!(
+        changes,
+        vec![MarkerChange::Add(0, "E103 main.whiley 1,4".to_string())]
+    );
+    assert_eq!(
+        tree.to_string(),
+        "====\n>>> main.whiley\nskip\n---\nE101 main.whiley 1,2\nE102 main.whiley 1,3\nE103 main.whiley 1,4\n"
+    );
+    // Reordering alone changes nothing
+    let wtf = parse("====\n---\nE103 main.whiley 1,4\nE101 main.whiley 1,2\nE102 main.whiley 1,3");
+    let changes = tree.set_markers(0, &wtf.frame(0).markers);
+    assert!(changes.is_empty());
+    assert!(tree
+        .to_string()
+        .ends_with("E102 main.whiley 1,3\nE103 main.whiley 1,4\n"));
+}
+
+#[test]
+fn bless_01() {
+    let input = "x = 1\n\n====\n>>> main.whiley\nskip \n---\nE101 main.whiley 1,2 \nE102 main.whiley 1,3\n========\n<<< main.whiley\n";
+    let mut tree = SyntaxTree::new(input);
+    let actual = parse("====\n---\nE101 main.whiley 1,2\nE103 main.whiley 1,4\n====\n");
+    let markers: Vec<_> = actual.iter().map(|f| f.markers.clone()).collect();
+    let changes = tree.bless(&markers);
+    assert_eq!(
+        tree.to_string(),
+        "x = 1\n\n====\n>>> main.whiley\nskip \n---\nE101 main.whiley 1,2 \nE103 main.whiley 1,4\n========\n<<< main.whiley\n"
+    );
+    assert_eq!(
+        changes,
+        vec![
+            MarkerChange::Remove(0, "E102 main.whiley 1,3".to_string()),
+            MarkerChange::Add(0, "E103 main.whiley 1,4".to_string())
+        ]
+    );
+}
+
+#[test]
+fn bless_02() {
+    // Frames without markers are unchanged
+    let input =
+        "====\r\n>>> main.whiley\r\nskip\r\n---\r\nE1 main.whiley 1,0\r\n====\r\n<<< main.whiley";
+    let mut tree = SyntaxTree::new(input);
+    let changes = tree.bless(&[vec![], vec![]]);
+    assert_eq!(
+        tree.to_string(),
+        "====\r\n>>> main.whiley\r\nskip\r\n---\r\n====\r\n<<< main.whiley"
+    );
+    assert_eq!(
+        changes,
+        vec![MarkerChange::Remove(0, "E1 main.whiley 1,0".to_string())]
+    );
+    assert_eq!(tree.bless(&[vec![], vec![]]), vec![]);
+}
+
+// ===============================================================
+// Snapshot Tests
+// ===============================================================
+
+#[test]
+fn snapshot_01() {
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+method main():
+>>> other.whiley
+import main
+====
+<<< other.whiley
+>>> main.whiley 1:1
+method main()
+    skip"#,
+    );
+    let mut s = Snapshot::new();
+    s.apply(wtf.frame(0)).unwrap();
+    assert!(s.size() == 2);
+    assert!(s.get("main.whiley") == Some(&["method main():"][..]));
+    assert!(s.get("other.whiley") == Some(&["import main"][..]));
+    s.apply(wtf.frame(1)).unwrap();
+    assert!(s.size() == 1);
+    assert!(!s.contains("other.whiley"));
+    assert!(s.get("main.whiley") == Some(&["method main()", "    skip"][..]));
+}
+
+#[test]
+fn snapshot_02() {
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+a
+b
+c
+d
+====
+>>> main.whiley 2:3
+x
+====
+>>> main.whiley 3
+y
+z
+====
+>>> main.whiley
+e"#,
+    );
+    let mut s = Snapshot::new();
+    s.apply(wtf.frame(0)).unwrap();
+    s.apply(wtf.frame(1)).unwrap();
+    assert!(s.get("main.whiley") == Some(&["a", "x", "d"][..]));
+    s.apply(wtf.frame(2)).unwrap();
+    assert!(s.get("main.whiley") == Some(&["a", "x", "y", "z"][..]));
+    s.apply(wtf.frame(3)).unwrap();
+    assert!(s.get("main.whiley") == Some(&["e"][..]));
+    let files: Vec<&str> = s.iter().map(|(n, _)| n).collect();
+    assert!(files == vec!["main.whiley"]);
+}
+
+#[test]
+fn snapshot_invalid_01() {
+    let wtf = parse("====\n>>> main.whiley 1\nx");
+    let mut s = Snapshot::new();
+    let err = s.apply(wtf.frame(0)).err().unwrap();
+    assert!(err == SnapshotError::InsertMissingFile("main.whiley".to_string()));
+}
+
+#[test]
+fn snapshot_invalid_02() {
+    let wtf = parse("====\n<<< main.whiley");
+    let mut s = Snapshot::new();
+    let err = s.apply(wtf.frame(0)).err().unwrap();
+    assert!(err == SnapshotError::RemoveMissingFile("main.whiley".to_string()));
+}
+
+#[test]
+fn snapshot_07() {
+    // Empty ranges insert without replacing
+    let wtf = parse(
+        "====\n>>> main.whiley\n====\n>>> main.whiley 1:0\nb\n====\n>>> main.whiley 1:0\na\n====\n>>> main.whiley 3:2\nd\n====\n>>> main.whiley 3:2\nc",
+    );
+    let snapshots: Vec<Snapshot> = wtf.snapshots().map(|s| s.unwrap()).collect();
+    assert_eq!(snapshots[0].get("main.whiley"), Some(&[][..]));
+    assert_eq!(snapshots[1].get("main.whiley"), Some(&["b"][..]));
+    assert_eq!(snapshots[2].get("main.whiley"), Some(&["a", "b"][..]));
+    assert_eq!(snapshots[3].get("main.whiley"), Some(&["a", "b", "d"][..]));
+    assert_eq!(
+        snapshots[4].get("main.whiley"),
+        Some(&["a", "b", "c", "d"][..])
+    );
+    assert!(wtf.validate().is_empty());
+}
+
+#[test]
+fn snapshot_invalid_03() {
+    check_out_of_bounds("0", Range(0, 0));
+    check_out_of_bounds("3", Range(3, 3));
+    check_out_of_bounds("2:3", Range(2, 3));
+    check_out_of_bounds("3:1", Range(3, 1));
+    check_out_of_bounds("4:3", Range(4, 3));
+    check_out_of_bounds("1:18446744073709551615", Range(1, usize::MAX));
+}
+
+#[test]
+fn snapshot_invalid_04() {
+    // Failed frames leave snapshot unchanged
+    let wtf = parse("====\n>>> main.whiley\nx\n====\n<<< main.whiley\n<<< main.whiley");
+    let mut s = Snapshot::new();
+    s.apply(wtf.frame(0)).unwrap();
+    assert!(s.apply(wtf.frame(1)).is_err());
+    assert!(s.get("main.whiley") == Some(&["x"][..]));
+}
+
+#[test]
+fn snapshots_01() {
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+method main():
+>>> other.whiley
+import main
+====
+<<< other.whiley
+>>> main.whiley 1:1
+method main()
+    skip"#,
+    );
+    let snapshots: Vec<Snapshot> = wtf.snapshots().map(|s| s.unwrap()).collect();
+    assert!(snapshots.len() == 2);
+    assert!(snapshots[0].size() == 2);
+    assert!(snapshots[0].get("main.whiley") == Some(&["method main():"][..]));
+    assert!(snapshots[1].size() == 1);
+    assert!(snapshots[1].get("main.whiley") == Some(&["method main()", "    skip"][..]));
+}
+
+#[test]
+fn snapshots_02() {
+    let wtf = parse("====\n>>> a.whiley\n====\n<<< b.whiley\n====\n<<< a.whiley");
+    let mut snapshots = wtf.snapshots();
+    assert!(snapshots.next().unwrap().is_ok());
+    assert!(snapshots.next().unwrap().is_err());
+    assert!(snapshots.next().is_none());
+}
+
+// ===============================================================
+// Materialiser Tests
+// ===============================================================
+
+#[test]
+fn materialise_01() {
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+method main():
+>>> src/other.whiley
+import main
+====
+<<< src/other.whiley
+>>> util.whiley
+type nat is (int x)
+====
+>>> main.whiley 1
+method main()
+    skip"#,
+    );
+    let dir = temp_dir("materialise_01");
+    let mut target = Materialiser::new(&dir);
+    let mut snapshots = wtf.snapshots().map(|s| s.unwrap());
+    // Frame 0
+    let changes = target.write(&snapshots.next().unwrap()).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::Write("main.whiley".to_string()),
+            Change::Write("src/other.whiley".to_string())
+        ]
+    );
+    assert_eq!(read(&dir, "main.whiley"), "method main():\n");
+    assert_eq!(read(&dir, "src/other.whiley"), "import main\n");
+    // Frame 1
+    let changes = target.write(&snapshots.next().unwrap()).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::Remove("src/other.whiley".to_string()),
+            Change::Write("util.whiley".to_string())
+        ]
+    );
+    assert!(!dir.join("src/other.whiley").exists());
+    // Frame 2
+    let changes = target.write(&snapshots.next().unwrap()).unwrap();
+    assert_eq!(changes, vec![Change::Write("main.whiley".to_string())]);
+    assert_eq!(read(&dir, "main.whiley"), "method main()\n    skip\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn materialise_02() {
+    let wtf = parse("====\n>>> a.whiley\nx\n>>> b.whiley\ny\n====\n<<< a.whiley");
+    let snapshots: Vec<Snapshot> = wtf.snapshots().map(|s| s.unwrap()).collect();
+    let dir = temp_dir("materialise_02");
+    Materialiser::new(&dir).write(&snapshots[0]).unwrap();
+    // Resume in a fresh materialiser
+    let mut target = Materialiser::new(&dir);
+    target.track(&snapshots[0]);
+    let changes = target.write(&snapshots[1]).unwrap();
+    assert_eq!(changes, vec![Change::Remove("a.whiley".to_string())]);
+    assert_eq!(read(&dir, "b.whiley"), "y\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn materialise_03() {
+    // Line terminators are written exactly
+    let wtf = parse("====\n>>> a.whiley\nx\r\ny\r\n>>> b.whiley\nz\n\\ No newline at end of file");
+    let dir = temp_dir("materialise_03");
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    Materialiser::new(&dir).write(&snapshot).unwrap();
+    assert_eq!(read(&dir, "a.whiley"), "x\r\ny\r\n");
+    assert_eq!(read(&dir, "b.whiley"), "z");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn materialise_04() {
+    // Directories left empty are removed
+    let wtf =
+        parse("====\n>>> a/b/c.whiley\nx\n>>> d/e.whiley\ny\n====\n<<< a/b/c.whiley\n>>> a\nz");
+    let dir = temp_dir("materialise_04");
+    fs::create_dir_all(dir.join("d")).unwrap();
+    let mut target = Materialiser::new(&dir);
+    for snapshot in wtf.snapshots() {
+        target.write(&snapshot.unwrap()).unwrap();
+    }
+    assert_eq!(read(&dir, "a"), "z\n");
+    assert!(dir.join("d/e.whiley").exists());
+    // Errors name the file concerned
+    fs::remove_file(dir.join("d/e.whiley")).unwrap();
+    fs::create_dir(dir.join("d/e.whiley")).unwrap();
+    let wtf = parse("====\n>>> d/e.whiley\ny");
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    let err = Materialiser::new(&dir).write(&snapshot).err().unwrap();
+    let path = dir.join("d/e.whiley").display().to_string();
+    assert!(err.to_string().starts_with(&path));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// ===============================================================
+// Validate Tests
+// ===============================================================
+
+#[test]
+fn validate_01() {
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+method main():
+    skip
+>>> other.whiley
+import main
+---
+E101 main.whiley 1,0:14
+W102 main.whiley 2,4
+====
+<<< other.whiley
+>>> main.whiley 1:1
+method main()
+---
+E103 main.whiley 2,0:8"#,
+    );
+    assert_eq!(wtf.validate(), vec![]);
+}
+
+#[test]
+fn validate_02() {
+    let wtf = parse(
+        "====
+>>> main.whiley
+x
+---
+E101 other.whiley 1,0",
+    );
+    check_violation(
+        &wtf,
+        0,
+        Item::Marker(0),
+        ViolationKind::MissingFile("other.whiley".to_string()),
+    );
+}
+
+#[test]
+fn validate_03() {
+    let wtf = parse(
+        "====
+>>> main.whiley
+x
+---
+E101 main.whiley 2,0",
+    );
+    check_violation(
+        &wtf,
+        0,
+        Item::Marker(0),
+        ViolationKind::LineOutOfBounds(2, 1),
+    );
+    let wtf = parse(
+        "====
+>>> main.whiley
+x
+---
+E101 main.whiley 0,0",
+    );
+    check_violation(
+        &wtf,
+        0,
+        Item::Marker(0),
+        ViolationKind::LineOutOfBounds(0, 1),
+    );
+}
+
+#[test]
+fn validate_04() {
+    let wtf = parse(
+        "====
+>>> main.whiley
+xyz
+---
+E101 main.whiley 1,2:4",
+    );
+    check_violation(
+        &wtf,
+        0,
+        Item::Marker(0),
+        ViolationKind::ColumnOutOfBounds(Range(2, 4), 3),
+    );
+}
+
+#[test]
+fn validate_05() {
+    let wtf = parse(
+        "====
+>>> main.whiley
+xyz
+---
+E101 main.whiley 1,2:1",
+    );
+    check_violation(
+        &wtf,
+        0,
+        Item::Marker(0),
+        ViolationKind::InvalidRange(Range(2, 1)),
+    );
+    let wtf = parse(
+        "====
+>>> main.whiley
+a
+b
+====
+>>> main.whiley 3:1
+c",
+    );
+    check_violation(
+        &wtf,
+        1,
+        Item::Action(0),
+        ViolationKind::InvalidRange(Range(3, 1)),
+    );
+}
+
+#[test]
+fn validate_06() {
+    let wtf = parse(
+        "====\n>>> main.whiley\na\nb\nc\n====\n>>> main.whiley 1\nx\n>>> main.whiley 3\ny\n>>> main.whiley 1:2\nz",
+    );
+    check_violation(
+        &wtf,
+        1,
+        Item::Action(2),
+        ViolationKind::OverlappingInsert(0),
+    );
+}
+
+#[test]
+fn validate_07() {
+    // Violations in later frames are still reported
+    let wtf = parse("====\n<<< main.whiley\n====\n>>> main.whiley 1\nx\n---\nE1 main.whiley 1,0");
+    let violations = wtf.validate();
+    assert_eq!(violations.len(), 3);
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::Snapshot(SnapshotError::RemoveMissingFile("main.whiley".to_string()))
+    );
+    assert_eq!(violations[1].frame, 1);
+    assert_eq!(violations[1].item, Item::Action(0));
+    assert_eq!(violations[2].item, Item::Marker(0));
+    assert_eq!(
+        violations[2].to_string(),
+        "frame 1, marker 0: missing file main.whiley"
+    );
+}
+
+#[test]
+fn validate_08() {
+    // Range ending at the largest index does not overflow
+    let wtf = parse("====\n>>> main.whiley\na\n====\n>>> main.whiley 1:18446744073709551615\nb");
+    check_violation(
+        &wtf,
+        1,
+        Item::Action(0),
+        ViolationKind::Snapshot(SnapshotError::RangeOutOfBounds(
+            "main.whiley".to_string(),
+            Range(1, usize::MAX),
+            1,
+        )),
+    );
+    assert!(wtf.snapshots().last().unwrap().is_err());
+}
+
+// ===============================================================
+// Owned Tests
+// ===============================================================
+
+#[test]
+fn owned_01() {
+    let input =
+        "x = \"y\"\n====\n>>> main.whiley\nmethod main()\n---\nE101 main.whiley 1,0\n".to_string();
+    let wtf = parse(&input);
+    let owned: WhileyTestFileBuf = wtf.to_owned();
+    assert_eq!(owned, wtf);
+    drop(wtf);
+    drop(input);
+    assert_eq!(owned.get_str("x"), Some("y"));
+    assert_eq!(owned.frame(0).markers[0].filename, "main.whiley");
+}
+
+#[test]
+fn owned_02() {
+    let input = "====\n>>> main.whiley\na\n====\n>>> main.whiley 1\nb\n".to_string();
+    let owned = parse(&input).into_owned();
+    // Owned test files can be sent across threads
+    let handle = std::thread::spawn(move || {
+        let s = owned.snapshots().last().unwrap().unwrap();
+        s.get("main.whiley").unwrap().join("\n")
+    });
+    assert_eq!(handle.join().unwrap(), "b");
+}
+
+#[test]
+fn owned_03() {
+    // Both forms print identically
+    let input = "a = 1\n====\n>>> main.whiley 1:2\nx\n<<< other.whiley\n";
+    let wtf = parse(input);
+    assert_eq!(wtf.to_string(), wtf.to_owned().to_string());
+}
+
+// ===============================================================
+// Builder Tests
+// ===============================================================
+
+#[test]
+fn builder_01() {
+    let wtf = WhileyTestFileBuilder::new()
+        .config("whiley.verify", false)
+        .config("boogie.timeout", 1000)
+        .config("name", "test")
+        .frame(|f| {
+            f.create("main.whiley", ["method main():"])
+                .create("other.whiley", vec!["import main".to_string()])
+                .expect_error(101, "main.whiley", Coordinate(1, Range(7, 10)))
+        })
+        .frame(|f| {
+            f.remove("other.whiley").insert(
+                "main.whiley",
+                Range(1, 1),
+                ["method main()", "    skip"],
+            )
+        })
+        .build()
+        .unwrap();
+    let expected = parse(
+        r#"whiley.verify = false
+boogie.timeout = 1000
+name = "test"
+====
+>>> main.whiley
+method main():
+>>> other.whiley
+import main
+---
+E101 main.whiley 1,7:10
+====
+<<< other.whiley
+>>> main.whiley 1
+method main()
+    skip"#,
+    );
+    assert_eq!(wtf, expected);
+    check_roundtrip(&wtf.to_string());
+}
+
+#[test]
+fn builder_02() {
+    let err = WhileyTestFileBuilder::new()
+        .config("a b", 1)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::InvalidConfigKey("a b".to_string()));
+    let err = WhileyTestFileBuilder::new()
+        .config("a", "\"")
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::InvalidConfigValue("\"".to_string()));
+}
+
+#[test]
+fn builder_03() {
+    let err = WhileyTestFileBuilder::new()
+        .frame(|f| f.create("main\nwhiley", ["x"]))
+        .build()
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err, BuildError::InvalidFilename(e) if e.kind == PathErrorKind::InvalidCharacter)
+    );
+    let err = WhileyTestFileBuilder::new()
+        .frame(|f| f.create("main.whiley", ["x", "y\nz"]))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::InvalidLine("y\nz".to_string()));
+}
+
+#[test]
+fn builder_04() {
+    let err = WhileyTestFileBuilder::new()
+        .frame(|f| f.create("main.whiley", ["x"]))
+        .frame(|f| f.expect_warning(1, "other.whiley", Coordinate(1, Range(0, 0))))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        BuildError::Violation(Violation {
+            frame: 1,
+            item: Item::Marker(0),
+            kind: ViolationKind::MissingFile("other.whiley".to_string())
+        })
+    );
+}
+
+#[test]
+fn builder_05() {
+    let err = WhileyTestFileBuilder::new()
+        .config("js.args", "a=b")
+        .frame(|f| f.create("main.whiley", ["x"]))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::InvalidConfigValue("a=b".to_string()));
+}
+
+#[test]
+fn builder_06() {
+    for c in ['1', 'E', ' '] {
+        let severity = Severity::Unknown(c);
+        let err = WhileyTestFileBuilder::new()
+            .frame(|f| {
+                f.create("main.whiley", ["x"]).expect(
+                    severity,
+                    1,
+                    "main.whiley",
+                    Coordinate(1, Range(0, 0)),
+                )
+            })
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(err, BuildError::InvalidSeverity(severity));
+    }
+}
+
+// ===============================================================
+// Mutation Tests
+// ===============================================================
+
+#[test]
+fn mutate_01() {
+    let mut wtf = parse("====\n>>> a.whiley\nx\n====\n>>> b.whiley\ny\n====\n<<< a.whiley");
+    let frame = wtf.remove_frame(1);
+    assert_eq!(wtf.size(), 2);
+    wtf.insert_frame(0, frame);
+    check_mutated(
+        &wtf,
+        "====\n>>> b.whiley\ny\n====\n>>> a.whiley\nx\n====\n<<< a.whiley",
+    );
+    wtf.frames_mut().swap(0, 1);
+    check_mutated(
+        &wtf,
+        "====\n>>> a.whiley\nx\n====\n>>> b.whiley\ny\n====\n<<< a.whiley",
+    );
+}
+
+#[test]
+fn mutate_02() {
+    let mut wtf = parse("====\n>>> a.whiley\nx\n---\nE1 a.whiley 1,0");
+    let markers = parse("====\n---\nE2 a.whiley 1,1\nW3 a.whiley 1,0")
+        .remove_frame(0)
+        .markers;
+    wtf.frame_mut(0).markers = markers;
+    for frame in wtf.iter_mut() {
+        frame.actions.push(Action::REMOVE(path("a.whiley")));
+    }
+    check_mutated(
+        &wtf,
+        "====\n>>> a.whiley\nx\n<<< a.whiley\n---\nE2 a.whiley 1,1\nW3 a.whiley 1,0",
+    );
+}
+
+#[test]
+fn mutate_03() {
+    let mut wtf =
+        parse("====\n>>> a.whiley\nx\n>>> b.whiley\ny\n<<< a.whiley\n---\nE1 b.whiley 1,0");
+    wtf.split_frame(0, 1);
+    check_mutated(
+        &wtf,
+        "====\n>>> a.whiley\nx\n====\n>>> b.whiley\ny\n<<< a.whiley\n---\nE1 b.whiley 1,0",
+    );
+    wtf.merge_frames(0);
+    check_mutated(
+        &wtf,
+        "====\n>>> a.whiley\nx\n>>> b.whiley\ny\n<<< a.whiley\n---\nE1 b.whiley 1,0",
+    );
+}
+
+#[test]
+fn mutate_04() {
+    let mut wtf = parse("====\n>>> a.whiley\nx\n---\nE1 a.whiley 1,0\n====\n<<< a.whiley");
+    wtf.merge_frames(0);
+    check_mutated(&wtf, "====\n>>> a.whiley\nx\n<<< a.whiley");
+    wtf.insert_frame(
+        1,
+        Frame {
+            actions: vec![],
+            markers: vec![],
+        },
+    );
+    check_mutated(&wtf, "====\n>>> a.whiley\nx\n<<< a.whiley\n====");
+}
+
+// ===============================================================
+// Compare Tests
+// ===============================================================
+
+#[test]
+fn compare_01() {
+    let expected = markers("E101 main.whiley 1,2\nE102 main.whiley 3,4");
+    let actual = markers("E102 main.whiley 3,4\nE101 main.whiley 1,2");
+    let cmp = compare(&expected, &actual);
+    assert!(cmp.is_match());
+    assert_eq!(cmp.matched.len(), 2);
+    assert_eq!(cmp.to_string(), "");
+}
+
+#[test]
+fn compare_02() {
+    // Duplicates matter
+    let expected = markers("E101 main.whiley 1,2\nE101 main.whiley 1,2");
+    let actual = markers("E101 main.whiley 1,2\nW5 other.whiley 1,1");
+    let cmp = compare(&expected, &actual);
+    assert!(!cmp.is_match());
+    assert_eq!(cmp.matched, markers("E101 main.whiley 1,2"));
+    assert_eq!(cmp.missing, markers("E101 main.whiley 1,2"));
+    assert_eq!(cmp.unexpected, markers("W5 other.whiley 1,1"));
+    assert_eq!(
+        cmp.to_string(),
+        "- E101 main.whiley 1,2 (missing)\n+ W5 other.whiley 1,1 (unexpected)\n"
+    );
+}
+
+#[test]
+fn compare_03() {
+    let expected = markers("E101 main.whiley 1,2\nE102 main.whiley 5,0\nE103 main.whiley 7,0");
+    let actual = markers("E102 main.whiley 9,0\nE102 main.whiley 6,0\nE104 main.whiley 1,2");
+    let cmp = compare(&expected, &actual);
+    let m = |s| markers(s).remove(0);
+    assert_eq!(
+        cmp.near_misses,
+        vec![
+            NearMiss::WrongCode {
+                expected: m("E101 main.whiley 1,2"),
+                actual: m("E104 main.whiley 1,2")
+            },
+            NearMiss::WrongLocation {
+                expected: m("E102 main.whiley 5,0"),
+                actual: m("E102 main.whiley 6,0")
+            }
+        ]
+    );
+    assert_eq!(cmp.missing, markers("E103 main.whiley 7,0"));
+    assert_eq!(cmp.unexpected, markers("E102 main.whiley 9,0"));
+    assert_eq!(
+        cmp.to_string(),
+        "- E103 main.whiley 7,0 (missing)\n+ E102 main.whiley 9,0 (unexpected)\n~ E101 main.whiley 1,2 -> E104 main.whiley 1,2 (wrong code)\n~ E102 main.whiley 5,0 -> E102 main.whiley 6,0 (wrong location)\n"
+    );
+}
+
+#[test]
+fn compare_04() {
+    // Same code in a different file is not a near miss
+    let expected = markers("E101 main.whiley 1,2");
+    let actual = markers("E101 other.whiley 1,2");
+    let cmp = compare(&expected, &actual);
+    assert!(cmp.near_misses.is_empty());
+    assert_eq!(cmp.missing.len(), 1);
+    assert_eq!(cmp.unexpected.len(), 1);
+}
+
+// ===============================================================
+// Path Tests
+// ===============================================================
+
+#[test]
+fn path_01() {
+    for p in [
+        "main.whiley",
+        "./main.whiley",
+        "src/../main.whiley",
+        ".//main.whiley/",
+    ] {
+        assert_eq!(path(p), "main.whiley");
+    }
+    assert_eq!(path("src\\util/./x.whiley"), "src/util/x.whiley");
+    // Paths already in normal form are not copied
+    assert!(matches!(
+        path("src/main.whiley").into_inner(),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[test]
+fn path_02() {
+    let p = path("src/util/main.whiley");
+    assert_eq!(
+        p.components().collect::<Vec<_>>(),
+        ["src", "util", "main.whiley"]
+    );
+    assert_eq!(p.parent(), Some("src/util"));
+    assert_eq!(p.file_name(), "main.whiley");
+    assert_eq!(p.resolve("build"), Path::new("build/src/util/main.whiley"));
+    let p = path("main.whiley");
+    assert_eq!(p.parent(), None);
+    assert_eq!(p.file_name(), "main.whiley");
+}
+
+#[test]
+fn path_invalid_01() {
+    let cases = [
+        ("", PathErrorKind::Empty),
+        ("./", PathErrorKind::Empty),
+        ("src/..", PathErrorKind::Empty),
+        ("/etc/passwd", PathErrorKind::Absolute),
+        ("\\main.whiley", PathErrorKind::Absolute),
+        ("C:/main.whiley", PathErrorKind::Absolute),
+        ("../main.whiley", PathErrorKind::Escaping),
+        ("src/../../main.whiley", PathErrorKind::Escaping),
+        ("main\nwhiley", PathErrorKind::InvalidCharacter),
+    ];
+    for (p, kind) in cases {
+        assert_eq!(TestPath::new(p).unwrap_err().kind, kind, "{:?}", p);
+    }
+}
+
+#[test]
+fn path_invalid_02() {
+    parse_expecting("====\n>>> ../../etc/passwd\nx", ErrorKind::InvalidFilename);
+    parse_expecting("====\n<<< /main.whiley", ErrorKind::InvalidFilename);
+    parse_expecting(
+        "====\n---\nE101 ../main.whiley 1,0",
+        ErrorKind::InvalidFilename,
+    );
+}
+
+#[test]
+fn path_03() {
+    // Differently written filenames identify the same file
+    let wtf = parse(
+        r#"
+====
+>>> ./main.whiley
+a
+>>> src/util.whiley
+b
+---
+E101 src/../main.whiley 1,0
+====
+>>> main.whiley 1
+c
+<<< src/./util.whiley"#,
+    );
+    assert_eq!(wtf.frame(0).actions[0].filename(), "main.whiley");
+    assert_eq!(wtf.frame(0).markers[0].filename, "main.whiley");
+    assert!(wtf.validate().is_empty());
+    let snapshot = wtf.snapshots().last().unwrap().unwrap();
+    assert_eq!(snapshot.get("./main.whiley"), Some(&["c"][..]));
+    assert!(!snapshot.contains("src/util.whiley"));
+    assert!(!snapshot.contains("../main.whiley"));
+    // Markers compare by normalised filename
+    let cmp = compare(&wtf.frame(0).markers, &markers("E101 ./main.whiley 1,0"));
+    assert!(cmp.is_match());
+}
+
+#[test]
+fn path_04() {
+    let err = WhileyTestFileBuilder::new()
+        .frame(|f| f.create("main.whiley", ["x"]).remove("../main.whiley"))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "invalid filename (path escapes project \"../main.whiley\")"
+    );
+}
+
+// ===============================================================
+// Newline Tests
+// ===============================================================
+
+#[test]
+fn newline_01() {
+    // Carriage returns are retained
+    let input =
+        "====\r\n>>> main.whiley\r\nmethod main():\r\n\\---\r\n---\r\nE101 main.whiley 1,0:13\r\n";
+    let wtf = parse(input);
+    let action = &wtf.frame(0).actions[0];
+    assert_eq!(action.lines(), ["method main():\r", "---\r"]);
+    assert!(action.ends_with_newline());
+    assert!(wtf.validate().is_empty());
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    assert_eq!(
+        snapshot.contents("main.whiley").unwrap(),
+        "method main():\r\n---\r\n"
+    );
+    check_roundtrip(input);
+    check_lossless(input);
+}
+
+#[test]
+fn newline_02() {
+    // Missing newline at end of file
+    let input = "====\n>>> main.whiley\na\nb\n\\ No newline at end of file\n";
+    let wtf = parse(input);
+    let action = &wtf.frame(0).actions[0];
+    assert_eq!(action.lines(), ["a", "b"]);
+    assert!(!action.ends_with_newline());
+    assert_eq!(
+        wtf.to_string(),
+        "================\n>>> main.whiley\na\nb\n\\ No newline at end of file\n---\n"
+    );
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    assert_eq!(snapshot.ends_with_newline("main.whiley"), Some(false));
+    assert_eq!(snapshot.contents("main.whiley").unwrap(), "a\nb");
+    check_lossless(input);
+}
+
+#[test]
+fn newline_03() {
+    // Empty file differs from a single blank line
+    let wtf =
+        parse("====\n>>> a.whiley\n>>> b.whiley\n\n>>> c.whiley\n\n\\ No newline at end of file");
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    assert_eq!(snapshot.get("a.whiley"), Some(&[][..]));
+    assert_eq!(snapshot.get("b.whiley"), Some(&[""][..]));
+    assert_eq!(snapshot.get("c.whiley"), Some(&[""][..]));
+    assert_eq!(snapshot.contents("a.whiley").unwrap(), "");
+    assert_eq!(snapshot.contents("b.whiley").unwrap(), "\n");
+    assert_eq!(snapshot.contents("c.whiley").unwrap(), "");
+    assert_eq!(snapshot.ends_with_newline("a.whiley"), Some(true));
+    assert_eq!(snapshot.ends_with_newline("c.whiley"), Some(false));
+}
+
+#[test]
+fn newline_04() {
+    // Inserting at the end of a file determines its final newline
+    let wtf = parse(
+        "====\n>>> main.whiley\na\nb\n====\n>>> main.whiley 2\nc\n\\ No newline at end of file\n====\n>>> main.whiley 1\nd\n====\n>>> main.whiley 2\ne\n",
+    );
+    let contents: Vec<String> = wtf
+        .snapshots()
+        .map(|s| s.unwrap().contents("main.whiley").unwrap())
+        .collect();
+    assert_eq!(contents, ["a\nb\n", "a\nc", "d\nc", "d\ne\n"]);
+}
+
+#[test]
+fn newline_05() {
+    // Unterminated lines cannot be inserted before the end of a file
+    let wtf = parse(
+        "====\n>>> main.whiley\na\nb\n====\n>>> main.whiley 1\nc\n\\ No newline at end of file",
+    );
+    let mut snapshot = Snapshot::new();
+    snapshot.apply(wtf.frame(0)).unwrap();
+    assert_eq!(
+        snapshot.apply(wtf.frame(1)),
+        Err(SnapshotError::UnterminatedInsert(
+            "main.whiley".to_string(),
+            Range(1, 1)
+        ))
+    );
+}
+
+#[test]
+fn newline_06() {
+    // Content which looks like the marker is escaped
+    let lines = [
+        "\\ No newline at end of file",
+        "\\\\ No newline at end of file\r",
+    ];
+    let wtf = WhileyTestFileBuilder::new()
+        .frame(|f| f.create("main.whiley", lines))
+        .build()
+        .unwrap();
+    let output = wtf.to_string();
+    assert_eq!(
+        output,
+        "================\n>>> main.whiley\n\\\\ No newline at end of file\n\\\\\\ No newline at end of file\r\n---\n"
+    );
+    assert_eq!(parse(&output), wtf);
+}
+
+#[test]
+fn newline_07() {
+    let mut wtf = parse("====\r\n>>> main.whiley\r\na\r\nb\r\n\\ No newline at end of file\r\n====\n>>> main.whiley 1\nc\r\n");
+    wtf.normalise_line_endings();
+    assert_eq!(wtf.frame(0).actions[0].lines(), ["a", "b"]);
+    assert_eq!(wtf.frame(1).actions[0].lines(), ["c"]);
+    let contents: Vec<String> = wtf
+        .snapshots()
+        .map(|s| s.unwrap().contents("main.whiley").unwrap())
+        .collect();
+    assert_eq!(contents, ["a\nb\n", "c\nb\n"]);
+}
+
+#[test]
+fn newline_invalid_01() {
+    parse_expecting(
+        "====\n>>> main.whiley\n\\ No newline at end of file\n",
+        ErrorKind::UnexpectedNoNewline,
+    );
+}
+
+#[test]
+fn newline_invalid_02() {
+    let err = WhileyTestFile::new("====\n>>> main.whiley\na\n\\ No newline at end of file\r\nb\n")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, ErrorKind::UnexpectedNoNewline);
+    assert_eq!(err.line, 4);
+    assert_eq!(err.text, "\\ No newline at end of file");
+}
+
+#[test]
+fn newline_invalid_03() {
+    // Span excludes the carriage return
+    let err =
+        WhileyTestFile::new("====\r\n>>> main.whiley\r\n\\ No newline at end of file\r\nfoo\r\n")
+            .err()
+            .unwrap();
+    assert_eq!(err.kind, ErrorKind::UnexpectedNoNewline);
+    assert_eq!(err.token(), "\\ No newline at end of file");
+    assert!(err.to_string().starts_with("line 3, column 1: unexpected"));
+}
+
+// ===============================================================
+// Column Tests
+// ===============================================================
+
+#[test]
+fn columns_01() {
+    let line = "añ😀b";
+    let expected = [
+        (ColumnUnit::Byte, 8, [0, 1, 3, 7, 8]),
+        (ColumnUnit::Char, 4, [0, 1, 2, 3, 4]),
+        (ColumnUnit::Utf16, 5, [0, 1, 2, 4, 5]),
+    ];
+    for (unit, width, cols) in expected {
+        let columns = Columns::new(unit, 1);
+        assert_eq!(columns.width(line), width);
+        for (offset, col) in [0, 1, 3, 7, 8].into_iter().zip(cols) {
+            assert_eq!(columns.offset(line, col), Some(offset));
+            assert_eq!(columns.column(line, offset), Some(col));
+        }
+        assert_eq!(columns.offset(line, width + 1), None);
+    }
+    // Columns and offsets within a character
+    assert_eq!(Columns::new(ColumnUnit::Byte, 1).offset(line, 2), None);
+    assert_eq!(Columns::new(ColumnUnit::Utf16, 1).offset(line, 3), None);
+    assert_eq!(Columns::default().column(line, 2), None);
+    assert_eq!(Columns::default().column(line, 9), None);
+}
+
+#[test]
+fn columns_02() {
+    // Tabs advance to the next tab stop
+    let columns = Columns::new(ColumnUnit::Char, 4);
+    let line = "\tx\ty";
+    assert_eq!(columns.width(line), 9);
+    assert_eq!(columns.offset(line, 4), Some(1));
+    assert_eq!(columns.offset(line, 5), Some(2));
+    assert_eq!(columns.offset(line, 8), Some(3));
+    assert_eq!(columns.offset(line, 6), None);
+    assert_eq!(columns.column(line, 3), Some(8));
+    assert_eq!(Columns::default().width(line), 4);
+}
+
+#[test]
+fn columns_03() {
+    assert_eq!(parse("").columns(), Ok(Columns::default()));
+    let wtf = parse("column.unit = \"byte\"\ncolumn.tab_width = 8\n");
+    assert_eq!(wtf.columns(), Ok(Columns::new(ColumnUnit::Byte, 8)));
+}
+
+#[test]
+fn columns_04() {
+    let wtf = parse("column.unit = \"utf16\"\n====\n>>> main.whiley\r\nx = \"😀\" + y\r\n");
+    let columns = wtf.columns().unwrap();
+    let snapshot = wtf.snapshots().next().unwrap().unwrap();
+    let check = |location: Coordinate, offsets: std::ops::Range<usize>| {
+        assert_eq!(
+            columns.to_offsets(&snapshot, "main.whiley", location),
+            Some(offsets.clone())
+        );
+        assert_eq!(
+            columns.to_coordinate(&snapshot, "main.whiley", location.0, offsets),
+            Some(location)
+        );
+    };
+    check(Coordinate(1, Range(0, 0)), 0..1);
+    check(Coordinate(1, Range(4, 7)), 4..10);
+    check(Coordinate(1, Range(11, 11)), 13..14);
+    check(Coordinate(1, Range(12, 12)), 14..14);
+    // Locations outside the file
+    assert_eq!(
+        columns.to_offsets(&snapshot, "main.whiley", Coordinate(1, Range(6, 7))),
+        None
+    );
+    assert_eq!(
+        columns.to_offsets(&snapshot, "main.whiley", Coordinate(1, Range(0, 13))),
+        None
+    );
+    assert_eq!(
+        columns.to_offsets(&snapshot, "main.whiley", Coordinate(2, Range(0, 0))),
+        None
+    );
+    assert_eq!(
+        columns.to_offsets(&snapshot, "other.whiley", Coordinate(1, Range(0, 0))),
+        None
+    );
+    assert_eq!(
+        columns.to_coordinate(&snapshot, "main.whiley", 1, 6..8),
+        None
+    );
+}
+
+#[test]
+fn columns_05() {
+    // Validation uses the declared columns
+    let input = "====\n>>> main.whiley\n\t\"😀\"\n---\nE101 main.whiley 1,5:6\n";
+    let violations = parse(input).validate();
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::ColumnOutOfBounds(Range(5, 6), 4)
+    );
+    let wtf = parse_owned(format!("column.unit = \"utf16\"\n{}", input));
+    assert_eq!(
+        wtf.validate()[0].kind,
+        ViolationKind::ColumnOutOfBounds(Range(5, 6), 5)
+    );
+    let wtf = parse_owned(format!(
+        "column.unit = \"utf16\"\ncolumn.tab_width = 2\n{}",
+        input
+    ));
+    assert!(wtf.validate().is_empty());
+}
+
+#[test]
+fn columns_invalid_01() {
+    for input in [
+        "column.unit = \"utf8\"",
+        "column.unit = 1",
+        "column.tab_width = 0",
+        "column.tab_width = \"4\"",
+    ] {
+        let err = parse(input).columns().err().unwrap();
+        assert_eq!(err.kind, OptionErrorKind::InvalidValue);
+        assert_eq!(err.key, input.split(' ').next().unwrap());
+    }
+}
+
+#[test]
+fn columns_invalid_02() {
+    // Invalid columns are reported, and defaults used instead
+    let wtf = parse(
+        "column.tab_width = 0
+====
+>>> main.whiley
+ab
+---
+E1 main.whiley 1,3",
+    );
+    let violations = wtf.validate();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].item, Item::Config);
+    assert!(matches!(
+        &violations[0].kind,
+        ViolationKind::InvalidOption(e) if e.key == "column.tab_width"
+    ));
+    assert_eq!(
+        violations[0].to_string(),
+        "configuration: invalid value for option `column.tab_width`"
+    );
+    assert_eq!(
+        violations[1].kind,
+        ViolationKind::ColumnOutOfBounds(Range(3, 3), 2)
+    );
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn path(filename: &str) -> TestPath<'_> {
+    TestPath::new(filename).unwrap()
+}
+
+fn parse_owned(input: String) -> WhileyTestFileBuf {
+    parse(&input).into_owned()
+}
+
+fn parse(input: &str) -> WhileyTestFile<'_> {
+    // Parser test file
+    let wtf = WhileyTestFile::new(input);
+    // Assume parsing succeeded
+    assert!(wtf.is_ok());
+    // Done
+    wtf.unwrap()
+}
+
+fn parse_config_option(input: &str, key: &str, val: Value) {
+    // Parser test file
+    let wtf = WhileyTestFile::new(input).unwrap();
+    // Look for value
+    match wtf.get(key) {
+        Some(v) => {
+            if &val != v {
+                panic!(
+                    "Expecting config value {:?} for key {:?}, got {:?}!",
+                    val, key, v
+                );
+            }
+        }
+        _ => {
+            panic!(
+                "Expected config value {:?} for key {:?}, got nothing!",
+                val, key
+            );
+        }
+    }
+}
+
+fn parse_expecting(input: &str, expected: ErrorKind) {
+    // Parser test file
+    match WhileyTestFile::new(input) {
+        Ok(_) => {
+            panic!("File should not have parsed!");
+        }
+        Err(err) => {
+            if err.kind != expected {
+                panic!("Expected error {:?}, got {:?}!", expected, err);
+            }
+        }
+    }
+}
+
+fn check_roundtrip(input: &str) {
+    let wtf = parse(input);
+    let output = wtf.to_string();
+    let wtf2 = parse(&output);
+    assert_eq!(wtf, wtf2);
+}
+
+fn check_lossless(input: &str) {
+    let tree = SyntaxTree::new(input);
+    assert_eq!(tree.to_string(), input);
+    // Parsing the tree must agree with parsing the input
+    assert_eq!(tree.parse(), WhileyTestFile::new(input));
+}
+
+fn check_out_of_bounds(range: &str, expected: Range) {
+    let input = format!(
+        "====\n>>> main.whiley\na\nb\n====\n>>> main.whiley {}\nx",
+        range
+    );
+    let wtf = parse(&input);
+    let mut s = Snapshot::new();
+    s.apply(wtf.frame(0)).unwrap();
+    let err = s.apply(wtf.frame(1)).err().unwrap();
+    assert_eq!(
+        err,
+        SnapshotError::RangeOutOfBounds("main.whiley".to_string(), expected, 2)
+    );
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn read(dir: &Path, filename: &str) -> String {
+    fs::read_to_string(dir.join(filename)).unwrap()
+}
+
+fn check_violation(wtf: &WhileyTestFile, frame: usize, item: Item, kind: ViolationKind) {
+    assert_eq!(wtf.validate(), vec![Violation { frame, item, kind }]);
+}
+
+fn check_mutated(wtf: &WhileyTestFile, expected: &str) {
+    assert_eq!(wtf, &parse(expected));
+    // Mutated test files must remain serialisable
+    assert_eq!(wtf, &parse(&wtf.to_string()));
+}
+
+fn markers(lines: &str) -> Vec<Marker<'static>> {
+    let input = format!("====\n---\n{}", lines);
+    parse(&input).into_owned().remove_frame(0).markers
 }
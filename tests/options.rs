@@ -0,0 +1,130 @@
+use whiley_test_file::options::{OptionErrorKind, Options};
+use whiley_test_file::WhileyTestFile;
+
+// ===============================================================
+// Options Tests
+// ===============================================================
+
+#[test]
+fn options_01() {
+    check_args(Options::wyc(), "", &[]);
+}
+
+#[test]
+fn options_02() {
+    check_args(
+        Options::wyc(),
+        "boogie.timeout = 1000\nwhiley.verify = true\n",
+        &["--verify", "--timeout", "1000"],
+    );
+}
+
+#[test]
+fn options_03() {
+    let options = Options::new()
+        .negated("wyc.compile", "--no-compile")
+        .string("js.target", "--target")
+        .with_default("js.target", "es6");
+    check_args(options.clone(), "", &["--target", "es6"]);
+    check_args(
+        options,
+        "wyc.compile = false\njs.target = \"es5\"\n",
+        &["--no-compile", "--target", "es5"],
+    );
+}
+
+#[test]
+fn options_04() {
+    // Later declarations replace earlier ones
+    let options = Options::new()
+        .int("boogie.timeout", "--timeout")
+        .int("boogie.timeout", "-t");
+    check_args(options, "boogie.timeout = 5\n", &["-t", "5"]);
+}
+
+#[test]
+fn options_05() {
+    let options = Options::wyc().ignore("js.").ignore("name");
+    check_args(options, "js.target = \"es6\"\nname = \"x\"\n", &[]);
+}
+
+#[test]
+fn options_06() {
+    // Column options are not special
+    let input = "column.unit = \"utf16\"\ncolumn.tab_width = 4\n";
+    check_args(Options::new().ignore("column."), input, &[]);
+    check_error(Options::new(), input, OptionErrorKind::UnknownOption);
+}
+
+#[test]
+fn options_07() {
+    assert_eq!(Options::default(), Options::new());
+    check_args(Options::default(), "", &[]);
+}
+
+#[test]
+fn options_invalid_01() {
+    check_error(
+        Options::wyc(),
+        "whiley.verfy = true\n",
+        OptionErrorKind::UnknownOption,
+    );
+}
+
+#[test]
+fn options_invalid_02() {
+    check_error(
+        Options::wyc(),
+        "boogie.timeout = true\n",
+        OptionErrorKind::InvalidValue,
+    );
+}
+
+#[test]
+fn options_invalid_03() {
+    check_error(
+        Options::wyc(),
+        "whiley.verify = \"yes\"\n",
+        OptionErrorKind::InvalidValue,
+    );
+}
+
+#[test]
+fn options_invalid_04() {
+    // Ignoring a namespace requires the trailing dot
+    let options = Options::wyc().ignore("js");
+    check_error(
+        options,
+        "js.target = \"es6\"\n",
+        OptionErrorKind::UnknownOption,
+    );
+}
+
+#[test]
+fn options_invalid_05() {
+    let wtf = WhileyTestFile::new("boogie.timeout = \"x\"\n").unwrap();
+    let err = Options::wyc().args(&wtf).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value for option `boogie.timeout`");
+}
+
+#[test]
+#[should_panic]
+fn options_invalid_06() {
+    let _ = Options::new()
+        .int("boogie.timeout", "--timeout")
+        .with_default("boogie.timeout", true);
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn check_args(options: Options, input: &str, expected: &[&str]) {
+    let wtf = WhileyTestFile::new(input).unwrap();
+    assert_eq!(options.args(&wtf).unwrap(), expected);
+}
+
+fn check_error(options: Options, input: &str, kind: OptionErrorKind) {
+    let wtf = WhileyTestFile::new(input).unwrap();
+    assert_eq!(options.args(&wtf).unwrap_err().kind, kind);
+}
@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::PathBuf;
+use whiley_test_file::syntax::SyntaxTree;
 use whiley_test_file::WhileyTestFile;
 
 pub static REFTESTS_DIR: &str = "reference-tests/tests";
@@ -9,7 +10,8 @@ include!(concat!(env!("OUT_DIR"), "/reftests.rs"));
 
 /// Run a specific test by loading the file out of the reference tests
 /// repository and attempting to parse it.  All reference tests should
-/// parse correctly.
+/// parse correctly, should survive being printed and reparsed, and
+/// should be reproduced exactly by their syntax tree.
 fn check(test: &str) {
     // Construct filename
     let mut path = PathBuf::from(REFTESTS_DIR);
@@ -21,4 +23,10 @@ fn check(test: &str) {
     let wtf = WhileyTestFile::new(&input);
     // Assume parsing succeeded
     assert!(wtf.is_ok());
+    let wtf = wtf.unwrap();
+    // Print and reparse test file
+    let output = wtf.to_string();
+    assert_eq!(wtf, WhileyTestFile::new(&output).unwrap());
+    // Check syntax tree is lossless
+    assert_eq!(SyntaxTree::new(&input).to_string(), input);
 }
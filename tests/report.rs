@@ -0,0 +1,168 @@
+use std::time::Duration;
+use whiley_test_file::report::{JUnit, Tap};
+use whiley_test_file::runner::{FrameResult, Outcome, TestResult};
+use whiley_test_file::{compare, WhileyTestFile};
+
+// ===============================================================
+// JUnit Tests
+// ===============================================================
+
+#[test]
+fn junit_01() {
+    assert_eq!(
+        JUnit(&[]).to_string(),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="0" failures="0" errors="0" time="0.000">
+</testsuites>
+"#
+    );
+}
+
+#[test]
+fn junit_02() {
+    assert_eq!(
+        JUnit(&results()).to_string(),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="4" failures="2" errors="1" time="3.750">
+  <testsuite name="a.test" tests="2" failures="1" errors="0" time="0.750">
+    <testcase name="frame 0" classname="a.test" time="0.250"/>
+    <testcase name="frame 1" classname="a.test" time="0.500">
+      <failure type="fail" message="a.test frame 1: markers differ">- E101 main.whiley 1,2 (missing)
++ E102 &lt;b&gt;.whiley 3,0 (unexpected)
+</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="dir/b&amp;c.test" tests="2" failures="1" errors="1" time="3.000">
+    <testcase name="frame 0" classname="dir/b&amp;c.test" time="3.000">
+      <failure type="timeout" message="dir/b&amp;c.test frame 0: timed out after 3.000s">timeout
+</failure>
+    </testcase>
+    <testcase name="frame 1" classname="dir/b&amp;c.test" time="0.000">
+      <error type="error" message="dir/b&amp;c.test frame 1: missing file &quot;x&quot;">missing file &quot;x&quot;
+</error>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+    );
+}
+
+#[test]
+fn junit_03() {
+    // Control characters cannot appear in XML
+    let output = JUnit(&coloured()).to_string();
+    assert!(output.contains(
+        "<error type=\"error\" message=\"c.test frame 0: \u{fffd}[31mboom\u{fffd}[0m\">"
+    ));
+    assert!(!output.contains('\x1b'));
+}
+
+// ===============================================================
+// TAP Tests
+// ===============================================================
+
+#[test]
+fn tap_01() {
+    assert_eq!(Tap(&[]).to_string(), "TAP version 13\n1..0\n");
+}
+
+#[test]
+fn tap_02() {
+    assert_eq!(
+        Tap(&results()).to_string(),
+        r#"TAP version 13
+1..4
+ok 1 - a.test frame 0
+not ok 2 - a.test frame 1
+  ---
+  message: "a.test frame 1: markers differ"
+  file: "a.test"
+  frame: 1
+  outcome: fail
+  details: |
+    - E101 main.whiley 1,2 (missing)
+    + E102 <b>.whiley 3,0 (unexpected)
+  ...
+not ok 3 - dir/b&c.test frame 0
+  ---
+  message: "dir/b&c.test frame 0: timed out after 3.000s"
+  file: "dir/b&c.test"
+  frame: 0
+  outcome: timeout
+  details: |
+    timeout
+  ...
+not ok 4 - dir/b&c.test frame 1
+  ---
+  message: "dir/b&c.test frame 1: missing file \"x\""
+  file: "dir/b&c.test"
+  frame: 1
+  outcome: error
+  details: |
+    missing file "x"
+  ...
+"#
+    );
+}
+
+#[test]
+fn tap_03() {
+    // Control characters are escaped in YAML
+    assert_eq!(
+        Tap(&coloured()).to_string(),
+        "TAP version 13
+1..1
+not ok 1 - c.test frame 0
+  ---
+  message: \"c.test frame 0: \\u001b[31mboom\\u001b[0m\"
+  file: \"c.test\"
+  frame: 0
+  outcome: error
+  details: |
+    \u{fffd}[31mboom\u{fffd}[0m
+  ...
+"
+    );
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn results() -> Vec<TestResult> {
+    let expected = WhileyTestFile::new("====\n---\nE101 main.whiley 1,2").unwrap();
+    let actual = WhileyTestFile::new("====\n---\nE102 <b>.whiley 3,0").unwrap();
+    let cmp = compare(&expected.frame(0).markers, &actual.frame(0).markers).into_owned();
+    let frame = |frame, outcome, millis| FrameResult {
+        frame,
+        outcome,
+        duration: Duration::from_millis(millis),
+    };
+    vec![
+        TestResult {
+            name: "a.test".to_string(),
+            frames: vec![
+                frame(0, Outcome::Pass, 250),
+                frame(1, Outcome::Fail(cmp), 500),
+            ],
+        },
+        TestResult {
+            name: "dir/b&c.test".to_string(),
+            frames: vec![
+                frame(0, Outcome::Timeout, 3000),
+                frame(1, Outcome::Error("missing file \"x\"".to_string()), 0),
+            ],
+        },
+    ]
+}
+
+fn coloured() -> Vec<TestResult> {
+    vec![TestResult {
+        name: "c.test".to_string(),
+        frames: vec![FrameResult {
+            frame: 0,
+            outcome: Outcome::Error("\x1b[31mboom\x1b[0m".to_string()),
+            duration: Duration::ZERO,
+        }],
+    }]
+}
@@ -0,0 +1,203 @@
+#![cfg(unix)]
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use whiley_test_file::diagnostics::TextParser;
+use whiley_test_file::options::Options;
+use whiley_test_file::runner::{Outcome, Runner, Summary};
+use whiley_test_file::WhileyTestFile;
+
+/// A stand-in for the Whiley compiler, which reports an error for
+/// every occurrence of `ERR` in the files given.
+static FAKE_WYC: &str = r#"#!/bin/sh
+for f in "$@"; do
+  awk -v f="$f" '/ERR/ {
+    c = index($0, "ERR"); s = "";
+    for (i = 1; i < c; i++) s = s " ";
+    print f ":" NR ": unknown variable"; print $0; print s "^^^"
+  }' "$f"
+done
+"#;
+
+// ===============================================================
+// Runner Tests
+// ===============================================================
+
+#[test]
+fn runner_01() {
+    let dir = temp_dir("runner_01");
+    let wtf = parse(
+        r#"
+====
+>>> main.whiley
+x ERR
+>>> lib/util.whiley
+ok
+---
+E201 main.whiley 1,2:4
+====
+>>> main.whiley 1
+x
+====
+>>> lib/util.whiley 1
+  ERR
+---
+E201 lib/util.whiley 1,2:4"#,
+    );
+    let result = runner(&dir, FAKE_WYC).run("runner_01", &wtf);
+    assert_eq!(result.frames.len(), 3);
+    assert!(result.passed());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_02() {
+    let dir = temp_dir("runner_02");
+    let wtf = parse(
+        "====\n>>> main.whiley\nERR\n---\nE201 main.whiley 2,0:2\n====\n>>> main.whiley 1\nx",
+    );
+    let result = runner(&dir, FAKE_WYC).run("runner_02", &wtf);
+    assert!(!result.passed());
+    match &result.frames[0].outcome {
+        Outcome::Fail(cmp) => assert_eq!(
+            cmp.to_string(),
+            "~ E201 main.whiley 2,0:2 -> E201 main.whiley 1,0:2 (wrong location)\n"
+        ),
+        o => panic!("unexpected outcome {:?}", o),
+    }
+    assert_eq!(result.frames[1].outcome, Outcome::Pass);
+    let mut summary = Summary::default();
+    summary.add(&result);
+    assert_eq!(
+        summary.to_string(),
+        "1 tests, 2 frames: 1 passed, 1 failed, 0 timed out, 0 errors"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_03() {
+    let dir = temp_dir("runner_03");
+    let wtf = parse("====\n>>> main.whiley\nx");
+    let runner = runner(&dir, "#!/bin/sh\nsleep 5\n").timeout(Duration::from_millis(100));
+    let result = runner.run("runner_03", &wtf);
+    assert_eq!(result.frames[0].outcome, Outcome::Timeout);
+    assert!(result.frames[0].duration < Duration::from_secs(5));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_04() {
+    // Frames which cannot be applied stop the test
+    let dir = temp_dir("runner_04");
+    let wtf = parse("====\n<<< main.whiley\n====\n>>> main.whiley\nx");
+    let result = runner(&dir, FAKE_WYC).run("runner_04", &wtf);
+    assert_eq!(result.frames.len(), 1);
+    assert!(matches!(result.frames[0].outcome, Outcome::Error(_)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_05() {
+    let dir = temp_dir("runner_05");
+    let wtf = parse("====\n>>> main.whiley\nx");
+    let missing = dir.join("missing").display().to_string();
+    let result = Runner::new(vec![missing], TextParser::new())
+        .root(dir.join("work"))
+        .run("runner_05", &wtf);
+    assert!(matches!(result.frames[0].outcome, Outcome::Error(_)));
+}
+
+#[test]
+fn runner_06() {
+    // Configuration options are passed before filenames
+    let dir = temp_dir("runner_06");
+    let script = "#!/bin/sh\n[ \"$1 $2 $3\" = \"--timeout 5 main.whiley\" ] && echo 'main.whiley:1: unknown variable\nx\n^'\n";
+    let wtf = parse("boogie.timeout = 5\n====\n>>> main.whiley\nx\n---\nE201 main.whiley 1,0");
+    let result = runner(&dir, script)
+        .options(Options::wyc())
+        .run("runner_06", &wtf);
+    assert!(result.passed());
+    // Unknown options prevent the test from running
+    let wtf = parse("boogie.timeot = 5\n====\n>>> main.whiley\nx");
+    let result = runner(&dir, script)
+        .options(Options::wyc())
+        .run("runner_06", &wtf);
+    assert_eq!(
+        result.frames[0].outcome,
+        Outcome::Error("unknown option `boogie.timeot`".to_string())
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_07() {
+    // The default root is removed afterwards
+    let dir = temp_dir("runner_07");
+    let root = std::env::temp_dir().join(format!("wtf-run-{}", std::process::id()));
+    let _ = runner(&dir, FAKE_WYC);
+    let path = dir.join("wyc.sh").display().to_string();
+    let wtf = parse("====\n>>> main.whiley\nx");
+    let result = Runner::new(vec![path], TextParser::new()).run("runner_07", &wtf);
+    assert!(result.passed());
+    assert!(!root.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_08() {
+    // Column options are not passed to the compiler
+    let dir = temp_dir("runner_08");
+    let script =
+        "#!/bin/sh\n[ \"$1\" = \"main.whiley\" ] || echo 'main.whiley:1: unknown variable\nx\n^'\n";
+    let wtf = parse("column.unit = \"utf16\"\n====\n>>> main.whiley\nx");
+    let result = runner(&dir, script)
+        .options(Options::wyc())
+        .run("runner_08", &wtf);
+    assert!(result.passed());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_09() {
+    // Names cannot escape the root
+    let dir = temp_dir("runner_09");
+    let root = dir.join("work");
+    let wtf = parse("====\n>>> main.whiley\nx");
+    for name in ["", ".", ".."] {
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.join("keep.txt"), "x").unwrap();
+        let result = runner(&dir, FAKE_WYC).run(name, &wtf);
+        assert!(result.passed());
+        assert!(root.is_dir());
+        assert!(dir.join("keep.txt").exists());
+        assert!(dir.join("wyc.sh").exists());
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn parse(input: &str) -> WhileyTestFile<'_> {
+    WhileyTestFile::new(input).unwrap()
+}
+
+/// Construct a runner for a given compiler script, which is written
+/// into the given directory.
+fn runner(dir: &Path, script: &str) -> Runner {
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("wyc.sh");
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    let parser = TextParser::new().message("unknown variable", 201);
+    Runner::new(vec![path.display().to_string()], parser).root(dir.join("work"))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
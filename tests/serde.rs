@@ -0,0 +1,159 @@
+#![cfg(feature = "serde")]
+use serde_json::json;
+use whiley_test_file::{compare, Severity, WhileyTestFile, WhileyTestFileBuf};
+
+// ===============================================================
+// Serde Tests
+// ===============================================================
+
+#[test]
+fn serde_01() {
+    let wtf = parse(
+        r#"whiley.verify = false
+boogie.timeout = 1000
+name = "x"
+====
+>>> main.whiley
+method main():
+---
+E101 main.whiley 1,2
+W302 main.whiley 1,2:3
+====
+<<< other.whiley
+>>> main.whiley 1:1
+method main()
+    skip
+"#,
+    );
+    let expected = json!({
+        "config": {
+            "boogie.timeout": 1000,
+            "name": "x",
+            "whiley.verify": false
+        },
+        "frames": [
+            {
+                "actions": [
+                    { "kind": "create", "filename": "main.whiley", "lines": ["method main():"] }
+                ],
+                "markers": [
+                    {
+                        "severity": "E",
+                        "errno": 101,
+                        "filename": "main.whiley",
+                        "location": { "line": 1, "range": [2, 2] }
+                    },
+                    {
+                        "severity": "W",
+                        "errno": 302,
+                        "filename": "main.whiley",
+                        "location": { "line": 1, "range": [2, 3] }
+                    }
+                ]
+            },
+            {
+                "actions": [
+                    { "kind": "remove", "filename": "other.whiley" },
+                    {
+                        "kind": "insert",
+                        "filename": "main.whiley",
+                        "range": [1, 1],
+                        "lines": ["method main()", "    skip"]
+                    }
+                ],
+                "markers": []
+            }
+        ]
+    });
+    assert_eq!(serde_json::to_value(&wtf).unwrap(), expected);
+    check_roundtrip(&wtf);
+}
+
+#[test]
+fn serde_02() {
+    // Configuration options are serialised in order
+    let wtf = parse("c = 1\na = 2\nb = 3\n");
+    assert_eq!(
+        serde_json::to_string(&wtf).unwrap(),
+        r#"{"config":{"a":2,"b":3,"c":1},"frames":[]}"#
+    );
+}
+
+#[test]
+fn serde_03() {
+    let input = r#"{"config":{},"frames":[{"actions":[{"kind":"create","filename":"a.whiley","lines":["x \"y\""]}],"markers":[]}]}"#;
+    let wtf: WhileyTestFileBuf = serde_json::from_str(input).unwrap();
+    assert_eq!(wtf.frame(0).actions[0].lines()[0], "x \"y\"");
+}
+
+#[test]
+fn serde_04() {
+    let input = r#"{"config":{},"frames":[{"actions":[{"kind":"move","filename":"a.whiley"}],"markers":[]}]}"#;
+    assert!(serde_json::from_str::<WhileyTestFileBuf>(input).is_err());
+}
+
+#[test]
+fn serde_05() {
+    let expected = parse("====\n---\nE101 main.whiley 1,2\nE102 main.whiley 3,0");
+    let actual = parse("====\n---\nE102 main.whiley 4,0");
+    let cmp = compare(&expected.frame(0).markers, &actual.frame(0).markers);
+    let m = |code: &str, errno: u16, line: usize, i: usize| {
+        json!({
+            "severity": code,
+            "errno": errno,
+            "filename": "main.whiley",
+            "location": { "line": line, "range": [i, i] }
+        })
+    };
+    assert_eq!(
+        serde_json::to_value(&cmp).unwrap(),
+        json!({
+            "matched": [],
+            "missing": [m("E", 101, 1, 2)],
+            "unexpected": [],
+            "near_misses": [
+                { "kind": "wrong_location", "expected": m("E", 102, 3, 0), "actual": m("E", 102, 4, 0) }
+            ]
+        })
+    );
+}
+
+#[test]
+fn serde_06() {
+    // Filenames are normalised, and rejected if invalid
+    let input = r#"{"config":{},"frames":[{"actions":[{"kind":"remove","filename":"./a.whiley"}],"markers":[]}]}"#;
+    let wtf: WhileyTestFileBuf = serde_json::from_str(input).unwrap();
+    assert_eq!(wtf.frame(0).actions[0].filename(), "a.whiley");
+    let input = r#"{"config":{},"frames":[{"actions":[{"kind":"remove","filename":"../a.whiley"}],"markers":[]}]}"#;
+    assert!(serde_json::from_str::<WhileyTestFileBuf>(input).is_err());
+}
+
+#[test]
+fn serde_07() {
+    // Severities must be letters
+    let marker = |severity: &str| {
+        format!(
+            r#"{{"config":{{}},"frames":[{{"actions":[],"markers":[{{"severity":"{}","errno":1,"filename":"a","location":{{"line":1,"range":[0,0]}}}}]}}]}}"#,
+            severity
+        )
+    };
+    let wtf: WhileyTestFileBuf = serde_json::from_str(&marker("X")).unwrap();
+    assert_eq!(wtf.frame(0).markers[0].severity, Severity::Unknown('X'));
+    for severity in ["1", " ", "é"] {
+        assert!(serde_json::from_str::<WhileyTestFileBuf>(&marker(severity)).is_err());
+    }
+}
+
+// ===============================================================
+// Helpers
+// ===============================================================
+
+fn parse(input: &str) -> WhileyTestFile<'_> {
+    WhileyTestFile::new(input).unwrap()
+}
+
+fn check_roundtrip(wtf: &WhileyTestFile) {
+    let json = serde_json::to_string(wtf).unwrap();
+    let wtf2: WhileyTestFileBuf = serde_json::from_str(&json).unwrap();
+    assert_eq!(wtf, &wtf2);
+}